@@ -0,0 +1,85 @@
+//! Code generation for `#[binrw]`.
+
+use crate::attrs::is_temp;
+use crate::{read, write};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Fields};
+
+/// Derives `BinRead` and `BinWrite` from the full definition, then emits the
+/// type without its `temp` fields or any `br`, `bw` and `brw` attributes.
+pub(crate) fn expand(args: TokenStream, input: &DeriveInput) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new(
+            args.span(),
+            "`binrw` takes no arguments; configure the type with `#[brw(...)]`",
+        ));
+    }
+    let read = read::derive(input)?;
+    let write = write::derive(input)?;
+
+    let mut input = input.clone();
+    strip_attrs(&mut input.attrs);
+    match &mut input.data {
+        Data::Struct(data) => strip_fields(&mut data.fields)?,
+        Data::Enum(data) => {
+            for variant in &mut data.variants {
+                strip_attrs(&mut variant.attrs);
+                strip_fields(&mut variant.fields)?;
+            }
+        }
+        Data::Union(_) => {}
+    }
+
+    Ok(quote! {
+        #input
+        #read
+        #write
+    })
+}
+
+/// Fails if any field is `temp`, which only `#[binrw]` can remove from the
+/// type.
+pub(crate) fn reject_temp(input: &DeriveInput) -> syn::Result<()> {
+    let fields: Vec<&syn::Field> = match &input.data {
+        Data::Struct(data) => data.fields.iter().collect(),
+        Data::Enum(data) => data.variants.iter().flat_map(|v| &v.fields).collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    for field in fields {
+        if is_temp(field)? {
+            return Err(syn::Error::new(
+                field.span(),
+                "`temp` fields need the `#[binrw]` attribute instead of the derives, so that the field can be removed from the type",
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn strip_attrs(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|attr| {
+        !["br", "bw", "brw"]
+            .iter()
+            .any(|name| attr.path().is_ident(name))
+    });
+}
+
+fn strip_fields(fields: &mut Fields) -> syn::Result<()> {
+    let list = match fields {
+        Fields::Named(fields) => &mut fields.named,
+        Fields::Unnamed(fields) => &mut fields.unnamed,
+        Fields::Unit => return Ok(()),
+    };
+    let mut kept = Punctuated::new();
+    for mut field in core::mem::take(list) {
+        if !is_temp(&field)? {
+            strip_attrs(&mut field.attrs);
+            kept.push(field);
+        }
+    }
+    *list = kept;
+    Ok(())
+}
@@ -0,0 +1,1045 @@
+//! Parsing of the `#[br]`, `#[bw]` and `#[brw]` attribute directives.
+
+use crate::named_args::{self, NamedField};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{ToTokens, quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::{
+    Attribute, DataEnum, DeriveInput, Expr, FieldValue, Fields, GenericParam, Generics, Ident,
+    Lifetime, LifetimeParam, Lit, Token, Type,
+};
+
+/// The lifetime used for the generated `Args<'_>` associated type.
+const ARGS_LIFETIME: &str = "'__binrw_args";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Read,
+    Write,
+}
+
+impl Direction {
+    fn accepts(self, attr: &Attribute) -> bool {
+        let path = attr.path();
+        path.is_ident("brw")
+            || match self {
+                Direction::Read => path.is_ident("br"),
+                Direction::Write => path.is_ident("bw"),
+            }
+    }
+
+    /// The stream variable in generated code.
+    pub(crate) fn stream(self) -> TokenStream {
+        match self {
+            Direction::Read => quote! { __binrw_reader },
+            Direction::Write => quote! { __binrw_writer },
+        }
+    }
+}
+
+/// Where a set of top-level options was found.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Scope {
+    Struct,
+    Enum,
+    Variant,
+}
+
+/// A single `name`, `name = expr`, `name(...)` or `name { ... }` directive.
+pub(crate) struct Directive {
+    pub(crate) name: Ident,
+    pub(crate) value: Value,
+    /// Whether the directive came from `brw` rather than `br` or `bw`.
+    pub(crate) shared: bool,
+}
+
+pub(crate) enum Value {
+    Flag,
+    Assign(Expr),
+    List(TokenStream),
+    Block(TokenStream),
+}
+
+impl Parse for Directive {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let name = Ident::parse_any(input)?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Value::Assign(input.parse()?)
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            Value::List(content.parse()?)
+        } else if input.peek(syn::token::Brace) {
+            let content;
+            syn::braced!(content in input);
+            Value::Block(content.parse()?)
+        } else {
+            Value::Flag
+        };
+        Ok(Self {
+            name,
+            value,
+            shared: false,
+        })
+    }
+}
+
+impl Directive {
+    pub(crate) fn is(&self, name: &str) -> bool {
+        self.name == name
+    }
+
+    pub(crate) fn span(&self) -> Span {
+        self.name.span()
+    }
+
+    pub(crate) fn error(&self, message: impl core::fmt::Display) -> syn::Error {
+        syn::Error::new(self.span(), message)
+    }
+
+    /// Whether the directive only means something when reading and came
+    /// from `brw`, so writing should pass over it.
+    pub(crate) fn is_read_only(&self) -> bool {
+        const READ_ONLY: [&str; 4] = [
+            "assert",
+            "temp",
+            "return_all_errors",
+            "return_unexpected_error",
+        ];
+        self.shared && READ_ONLY.iter().any(|name| self.is(name))
+    }
+
+    pub(crate) fn unknown(&self) -> syn::Error {
+        self.error(format_args!("unknown directive `{}`", self.name))
+    }
+
+    pub(crate) fn flag(&self) -> syn::Result<()> {
+        match self.value {
+            Value::Flag => Ok(()),
+            _ => Err(self.error(format_args!("`{}` does not take a value", self.name))),
+        }
+    }
+
+    pub(crate) fn expr(&self) -> syn::Result<Expr> {
+        match &self.value {
+            Value::Assign(expr) => Ok(expr.clone()),
+            Value::List(tokens) => syn::parse2(tokens.clone()),
+            _ => Err(self.error(format_args!("expected `{} = ...`", self.name))),
+        }
+    }
+
+    pub(crate) fn list<T: Parse>(&self) -> syn::Result<Vec<T>> {
+        match &self.value {
+            Value::List(tokens) => Ok(Punctuated::<T, Token![,]>::parse_terminated
+                .parse2(tokens.clone())?
+                .into_iter()
+                .collect()),
+            _ => Err(self.error(format_args!("expected `{}(...)`", self.name))),
+        }
+    }
+
+    pub(crate) fn block<T: Parse>(&self) -> syn::Result<Vec<T>> {
+        match &self.value {
+            Value::Block(tokens) => Ok(Punctuated::<T, Token![,]>::parse_terminated
+                .parse2(tokens.clone())?
+                .into_iter()
+                .collect()),
+            _ => Err(self.error(format_args!("expected `{} {{ ... }}`", self.name))),
+        }
+    }
+}
+
+/// Collects every directive from the attributes that apply to `direction`.
+pub(crate) fn directives(attrs: &[Attribute], direction: Direction) -> syn::Result<Vec<Directive>> {
+    let mut directives = Vec::new();
+    for attr in attrs.iter().filter(|attr| direction.accepts(attr)) {
+        let list = attr.meta.require_list()?;
+        let shared = attr.path().is_ident("brw");
+        directives.extend(
+            Punctuated::<Directive, Token![,]>::parse_terminated
+                .parse2(list.tokens.clone())?
+                .into_iter()
+                .map(|directive| Directive {
+                    shared,
+                    ..directive
+                }),
+        );
+    }
+    Ok(directives)
+}
+
+/// Whether a field has `temp`, so that it is read but not stored in the type.
+/// Only the `#[binrw]` attribute can remove such a field from the type.
+pub(crate) fn is_temp(field: &syn::Field) -> syn::Result<bool> {
+    Ok(directives(&field.attrs, Direction::Read)?
+        .iter()
+        .any(|directive| directive.is("temp")))
+}
+
+/// A byte order override from `big`, `little`, `is_big = ...` or
+/// `is_little = ...`.
+#[derive(Clone)]
+pub(crate) enum EndianSpec {
+    Big,
+    Little,
+    IsBig(Expr),
+    IsLittle(Expr),
+}
+
+impl EndianSpec {
+    /// Parses `directive` if it is an endian directive.
+    pub(crate) fn parse(directive: &Directive) -> syn::Result<Option<Self>> {
+        Ok(Some(if directive.is("big") {
+            directive.flag()?;
+            Self::Big
+        } else if directive.is("little") {
+            directive.flag()?;
+            Self::Little
+        } else if directive.is("is_big") {
+            Self::IsBig(directive.expr()?)
+        } else if directive.is("is_little") {
+            Self::IsLittle(directive.expr()?)
+        } else {
+            return Ok(None);
+        }))
+    }
+
+    pub(crate) fn set(slot: &mut Option<Self>, directive: &Directive) -> syn::Result<bool> {
+        match Self::parse(directive)? {
+            Some(_) if slot.is_some() => Err(directive.error("conflicting endianness")),
+            Some(endian) => {
+                *slot = Some(endian);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Produces the effective `Endian` given the inherited one.
+    pub(crate) fn resolve(spec: Option<&Self>, inherited: &TokenStream) -> TokenStream {
+        match spec {
+            None => inherited.clone(),
+            Some(Self::Big) => quote! { binrw::Endian::Big },
+            Some(Self::Little) => quote! { binrw::Endian::Little },
+            Some(Self::IsBig(cond)) => {
+                quote! { if #cond { binrw::Endian::Big } else { binrw::Endian::Little } }
+            }
+            Some(Self::IsLittle(cond)) => {
+                quote! { if #cond { binrw::Endian::Little } else { binrw::Endian::Big } }
+            }
+        }
+    }
+}
+
+/// A single `name: Type` entry of an `import(...)` list.
+pub(crate) struct Import {
+    pub(crate) name: Ident,
+    pub(crate) ty: Type,
+}
+
+impl Parse for Import {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        Ok(Self { name, ty })
+    }
+}
+
+/// The arguments a derived type accepts, declared with `import`,
+/// `import_raw` or `import { ... }`.
+#[derive(Default)]
+pub(crate) enum Imports {
+    #[default]
+    None,
+    List(Vec<Import>),
+    Raw(Box<Import>),
+    Named(Vec<NamedField>),
+}
+
+impl Imports {
+    pub(crate) fn set(&mut self, directive: &Directive) -> syn::Result<bool> {
+        let imports = if directive.is("import") {
+            match directive.value {
+                Value::Block(_) => Imports::Named(directive.block()?),
+                _ => Imports::List(directive.list()?),
+            }
+        } else if directive.is("import_raw") {
+            let mut list = directive.list::<Import>()?;
+            if list.len() != 1 {
+                return Err(directive.error("`import_raw` takes exactly one `name: Type`"));
+            }
+            Imports::Raw(Box::new(list.remove(0)))
+        } else {
+            return Ok(false);
+        };
+        if !matches!(self, Imports::None) {
+            return Err(directive.error("conflicting imports"));
+        }
+        *self = imports;
+        Ok(true)
+    }
+
+    /// The type used for `Args<'__binrw_args>`.
+    pub(crate) fn args_type(&self, owner: &DeriveInput, direction: Direction) -> TokenStream {
+        match self {
+            Imports::None => quote! { () },
+            Imports::List(list) => {
+                let types = list.iter().map(|import| with_args_lifetime(&import.ty));
+                quote! { (#(#types,)*) }
+            }
+            Imports::Raw(import) => with_args_lifetime(&import.ty).into_token_stream(),
+            Imports::Named(fields) => {
+                let ident = named_args_ident(owner, direction);
+                let generics = named_args_generics(owner, fields);
+                let (_, ty_generics, _) = generics.split_for_impl();
+                quote! { #ident #ty_generics }
+            }
+        }
+    }
+
+    /// Binds each import by name from `__binrw_args`.
+    pub(crate) fn bind(&self) -> Option<TokenStream> {
+        match self {
+            Imports::None => None,
+            Imports::List(list) => {
+                let names = list.iter().map(|import| &import.name);
+                Some(quote! { let (#(#names,)*) = __binrw_args; })
+            }
+            Imports::Raw(import) => {
+                let name = &import.name;
+                Some(quote! { let #name = __binrw_args; })
+            }
+            Imports::Named(fields) => {
+                let names = fields.iter().map(|field| &field.name);
+                Some(quote! { #(let #names = __binrw_args.#names;)* })
+            }
+        }
+    }
+
+    /// Defines the named arguments struct and its builder for `import { ... }`.
+    pub(crate) fn definition(
+        &self,
+        owner: &DeriveInput,
+        direction: Direction,
+    ) -> Option<TokenStream> {
+        let Imports::Named(fields) = self else {
+            return None;
+        };
+        let vis = &owner.vis;
+        let ident = named_args_ident(owner, direction);
+        let generics = named_args_generics(owner, fields);
+        let (_, _, where_clause) = generics.split_for_impl();
+        let names = fields.iter().map(|field| &field.name);
+        let types = fields.iter().map(|field| with_args_lifetime(&field.ty));
+        let fields = fields
+            .iter()
+            .map(|field| NamedField {
+                name: field.name.clone(),
+                ty: with_args_lifetime(&field.ty),
+                default: field.default.clone(),
+            })
+            .collect::<Vec<_>>();
+        let builder = named_args::builder(vis, &ident, &generics, &fields);
+        let doc = format!(
+            "Named arguments for the [`{}`] implementation of [`{}`].",
+            match direction {
+                Direction::Read => "BinRead",
+                Direction::Write => "BinWrite",
+            },
+            owner.ident,
+        );
+
+        Some(quote! {
+            #[doc = #doc]
+            #vis struct #ident #generics #where_clause {
+                #(#vis #names: #types,)*
+            }
+
+            #builder
+        })
+    }
+}
+
+fn named_args_ident(owner: &DeriveInput, direction: Direction) -> Ident {
+    match direction {
+        Direction::Read => quote::format_ident!("{}BinReadArgs", owner.ident),
+        Direction::Write => quote::format_ident!("{}BinWriteArgs", owner.ident),
+    }
+}
+
+/// The generics of a named arguments struct: the args lifetime if any import
+/// borrows, and whichever type parameters of the owner the imports mention.
+fn named_args_generics(owner: &DeriveInput, fields: &[NamedField]) -> Generics {
+    let types = fields
+        .iter()
+        .map(|field| with_args_lifetime(&field.ty).into_token_stream())
+        .collect::<TokenStream>();
+    let lifetime = args_lifetime();
+
+    let mut generics = Generics::default();
+    if mentions(&types, &lifetime.ident) {
+        generics
+            .params
+            .push(GenericParam::Lifetime(LifetimeParam::new(lifetime)));
+    }
+    let mut used = Vec::new();
+    for param in &owner.generics.params {
+        if let GenericParam::Type(param) = param
+            && mentions(&types, &param.ident)
+        {
+            generics.params.push(GenericParam::Type(param.clone()));
+            used.push(param.ident.clone());
+        }
+    }
+    if let Some(where_clause) = &owner.generics.where_clause {
+        let predicates = where_clause
+            .predicates
+            .iter()
+            .filter(|predicate| {
+                let tokens = predicate.to_token_stream();
+                used.iter().any(|ident| mentions(&tokens, ident))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !predicates.is_empty() {
+            generics.make_where_clause().predicates.extend(predicates);
+        }
+    }
+    generics
+}
+
+/// Whether `ident` appears anywhere in `tokens`.
+fn mentions(tokens: &TokenStream, ident: &Ident) -> bool {
+    tokens.clone().into_iter().any(|tree| match tree {
+        TokenTree::Ident(other) => other == *ident,
+        TokenTree::Group(group) => mentions(&group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Gives every elided reference lifetime in an imported type the args
+/// lifetime.
+fn with_args_lifetime(ty: &Type) -> Type {
+    struct Elided;
+
+    impl VisitMut for Elided {
+        fn visit_type_reference_mut(&mut self, ty: &mut syn::TypeReference) {
+            if ty.lifetime.is_none() {
+                ty.lifetime = Some(args_lifetime());
+            }
+            syn::visit_mut::visit_type_reference_mut(self, ty);
+        }
+
+        fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+            if lifetime.ident == "_" {
+                *lifetime = args_lifetime();
+            }
+        }
+    }
+
+    let mut ty = ty.clone();
+    Elided.visit_type_mut(&mut ty);
+    ty
+}
+
+pub(crate) fn args_lifetime() -> Lifetime {
+    Lifetime::new(ARGS_LIFETIME, Span::call_site())
+}
+
+/// The arguments passed to a field, from `args(...)`, `args { ... }`,
+/// `args_raw = ...` or `count = ...`.
+#[derive(Default)]
+pub(crate) enum FieldArgs {
+    #[default]
+    None,
+    Tuple(Vec<Expr>),
+    Named(Span, Vec<FieldValue>),
+    Raw(Expr),
+    /// The number of elements in a `Vec`. Writing a `Vec` needs no count, so
+    /// this only applies when reading.
+    Count(Expr),
+}
+
+impl FieldArgs {
+    pub(crate) fn set(&mut self, directive: &Directive) -> syn::Result<bool> {
+        let args = if directive.is("args") {
+            match directive.value {
+                Value::Block(_) => FieldArgs::Named(directive.span(), directive.block()?),
+                _ => FieldArgs::Tuple(directive.list()?),
+            }
+        } else if directive.is("args_raw") {
+            FieldArgs::Raw(directive.expr()?)
+        } else if directive.is("count") {
+            FieldArgs::Count(directive.expr()?)
+        } else {
+            return Ok(false);
+        };
+        if !matches!(self, FieldArgs::None) {
+            return Err(directive.error("conflicting args"));
+        }
+        *self = args;
+        Ok(true)
+    }
+
+    /// The arguments for a field of type `ty`, or for a value whose type is
+    /// only known to the compiler when `ty` is `None`.
+    pub(crate) fn tokens(&self, ty: Option<&Type>, direction: Direction) -> TokenStream {
+        match self {
+            FieldArgs::None => quote! { binrw::Required::args() },
+            FieldArgs::Tuple(exprs) => quote! { (#(#exprs,)*) },
+            FieldArgs::Raw(expr) => expr.to_token_stream(),
+            FieldArgs::Count(expr) => match direction {
+                Direction::Read => quote! { binrw::private::count(#expr) },
+                Direction::Write => quote! { binrw::Required::args() },
+            },
+            FieldArgs::Named(span, values) => {
+                let Some(ty) = ty else {
+                    return syn::Error::new(
+                        *span,
+                        "`args { ... }` needs the field type; use `args_raw` here instead",
+                    )
+                    .into_compile_error();
+                };
+                let binrw_trait = match direction {
+                    Direction::Read => quote! { binrw::BinRead },
+                    Direction::Write => quote! { binrw::BinWrite },
+                };
+                let setters = values.iter().map(|value| {
+                    let member = &value.member;
+                    let expr = &value.expr;
+                    quote! { .#member(#expr) }
+                });
+                quote! {
+                    <<#ty as #binrw_trait>::Args<'_> as binrw::NamedArgs>::builder()
+                        #(#setters)*
+                        .finalize()
+                }
+            }
+        }
+    }
+}
+
+/// A `magic = ...` value: an integer literal with a type suffix, or a byte
+/// string literal.
+#[derive(Clone)]
+pub(crate) struct Magic(Lit);
+
+impl Magic {
+    pub(crate) fn set(slot: &mut Option<Self>, directive: &Directive) -> syn::Result<bool> {
+        if !directive.is("magic") {
+            return Ok(false);
+        }
+        if slot.is_some() {
+            return Err(directive.error("conflicting magic"));
+        }
+        match directive.expr()? {
+            Expr::Lit(syn::ExprLit {
+                lit: Lit::Int(int), ..
+            }) if int.suffix().is_empty() => Err(syn::Error::new(
+                int.span(),
+                "magic literals need an explicit type suffix, e.g. `0x7fu8`",
+            )),
+            Expr::Lit(syn::ExprLit {
+                lit: lit @ (Lit::Int(_) | Lit::ByteStr(_)),
+                ..
+            }) => {
+                *slot = Some(Magic(lit));
+                Ok(true)
+            }
+            expr => Err(syn::Error::new_spanned(
+                expr,
+                "expected an integer or byte string literal",
+            )),
+        }
+    }
+
+    /// The magic as a value; byte strings become arrays.
+    pub(crate) fn value(&self) -> TokenStream {
+        match &self.0 {
+            Lit::ByteStr(bytes) => quote! { *#bytes },
+            lit => quote! { #lit },
+        }
+    }
+
+    /// Checks the magic, in the endianness `endian`.
+    pub(crate) fn read(&self, endian: &TokenStream) -> TokenStream {
+        let value = self.value();
+        quote! { binrw::private::magic(__binrw_reader, #value, #endian).await }
+    }
+
+    /// Emits the magic, in the endianness `endian`.
+    pub(crate) fn write(&self, endian: &TokenStream) -> TokenStream {
+        let value = self.value();
+        quote! { binrw::private::write_magic(__binrw_writer, #value, #endian).await }
+    }
+}
+
+/// The integer type of a fieldless enum's discriminant, from `repr = ...`.
+pub(crate) struct Repr(Type);
+
+impl Repr {
+    pub(crate) fn set(slot: &mut Option<Self>, directive: &Directive) -> syn::Result<bool> {
+        if !directive.is("repr") {
+            return Ok(false);
+        }
+        if slot.is_some() {
+            return Err(directive.error("conflicting repr"));
+        }
+        *slot = Some(Repr(syn::parse2(directive.expr()?.into_token_stream())?));
+        Ok(true)
+    }
+}
+
+/// An enum whose variants are read and written as an integer discriminant.
+pub(crate) struct UnitEnum<'a> {
+    pub(crate) repr: Type,
+    /// Each fieldless variant together with its discriminant expression.
+    pub(crate) variants: Vec<(&'a Ident, TokenStream)>,
+    /// The variant which holds any discriminant not matched by `variants`.
+    pub(crate) fallback: Option<&'a Ident>,
+}
+
+impl<'a> UnitEnum<'a> {
+    /// Returns the unit enum layout of `data`, or `None` if it should be
+    /// treated as a data enum.
+    ///
+    /// An explicit `repr = ...` directive always selects the unit layout. A
+    /// `#[repr(uN)]` attribute selects it only when every variant is either
+    /// fieldless or the `fallback`.
+    pub(crate) fn new(
+        input: &DeriveInput,
+        data: &'a DataEnum,
+        repr: Option<&Repr>,
+        direction: Direction,
+    ) -> syn::Result<Option<Self>> {
+        let explicit = repr.is_some();
+        let Some(repr) = repr.map(|Repr(ty)| ty.clone()).or_else(|| int_repr(input)) else {
+            return Ok(None);
+        };
+
+        let mut variants = Vec::new();
+        let mut fallback = None;
+        let mut discriminant = quote! { 0 };
+        for variant in &data.variants {
+            let is_fallback = is_fallback(variant, direction)?;
+            if let Some((_, expr)) = &variant.discriminant {
+                discriminant = expr.to_token_stream();
+            }
+            if is_fallback {
+                if fallback.is_some() {
+                    return Err(syn::Error::new(
+                        variant.ident.span(),
+                        "only one variant can be the fallback",
+                    ));
+                }
+                if !matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1)
+                {
+                    return Err(syn::Error::new(
+                        variant.ident.span(),
+                        "the fallback variant must hold exactly one unnamed field of the repr type",
+                    ));
+                }
+                fallback = Some(&variant.ident);
+            } else if matches!(variant.fields, Fields::Unit) {
+                variants.push((&variant.ident, quote! { (#discriminant) }));
+            } else if explicit {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "variants of an enum with `repr` cannot have fields",
+                ));
+            } else {
+                return Ok(None);
+            }
+            discriminant = quote! { (#discriminant) + 1 };
+        }
+
+        Ok(Some(Self {
+            repr,
+            variants,
+            fallback,
+        }))
+    }
+}
+
+/// Finds the integer type in a `#[repr(...)]` attribute.
+fn int_repr(input: &DeriveInput) -> Option<Type> {
+    const INTS: [&str; 12] = [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
+    let mut found = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        let _ = attr.parse_nested_meta(|meta| {
+            if INTS.iter().any(|int| meta.path.is_ident(int)) {
+                found = meta.path.get_ident().cloned();
+            }
+            Ok(())
+        });
+    }
+    found.map(|ident| syn::parse_quote!(#ident))
+}
+
+/// Checks a variant for the `fallback` directive, rejecting anything else.
+fn is_fallback(variant: &syn::Variant, direction: Direction) -> syn::Result<bool> {
+    let mut fallback = false;
+    for directive in directives(&variant.attrs, direction)? {
+        if !directive.is("fallback") {
+            return Err(directive.error(format_args!(
+                "`{}` is not valid on a variant of a unit enum",
+                directive.name
+            )));
+        }
+        directive.flag()?;
+        fallback = true;
+    }
+    Ok(fallback)
+}
+
+/// A field that is only present when `test` holds, from `if(test)` or
+/// `if(test, alternate)`. When reading a missing field, it takes the value
+/// `alternate`, or its default.
+pub(crate) struct Condition {
+    pub(crate) test: Expr,
+    pub(crate) alternate: Option<Expr>,
+}
+
+impl Condition {
+    pub(crate) fn set(slot: &mut Option<Self>, directive: &Directive) -> syn::Result<bool> {
+        if !directive.is("if") {
+            return Ok(false);
+        }
+        if slot.is_some() {
+            return Err(directive.error("duplicate `if`"));
+        }
+        let mut exprs = directive.list::<Expr>()?.into_iter();
+        let (Some(test), alternate, None) = (exprs.next(), exprs.next(), exprs.next()) else {
+            return Err(directive.error("expected `if(condition)` or `if(condition, alternate)`"));
+        };
+        *slot = Some(Condition { test, alternate });
+        Ok(true)
+    }
+}
+
+/// A check on the values read so far, from `assert(test)`,
+/// `assert(test, "format", args...)` or `assert(test, error)`.
+pub(crate) struct Assert {
+    test: Expr,
+    error: AssertError,
+}
+
+/// What a failed [`Assert`] reports.
+enum AssertError {
+    /// `Error::AssertFail` naming the test.
+    Default,
+    /// `Error::AssertFail` with a formatted message.
+    Message(Vec<Expr>),
+    /// `Error::Custom` holding a user error value.
+    Custom(Expr),
+}
+
+impl Assert {
+    pub(crate) fn set(asserts: &mut Vec<Self>, directive: &Directive) -> syn::Result<bool> {
+        if !directive.is("assert") {
+            return Ok(false);
+        }
+        let mut exprs = directive.list::<Expr>()?.into_iter();
+        let Some(test) = exprs.next() else {
+            return Err(directive.error("expected `assert(condition, ...)`"));
+        };
+        let rest = exprs.collect::<Vec<_>>();
+        let error = match rest.first() {
+            None => AssertError::Default,
+            Some(Expr::Lit(syn::ExprLit {
+                lit: Lit::Str(_), ..
+            })) => AssertError::Message(rest),
+            Some(error) if rest.len() == 1 => AssertError::Custom(error.clone()),
+            Some(_) => {
+                return Err(directive.error(
+                    "expected an error value or a format string after the `assert` condition",
+                ));
+            }
+        };
+        asserts.push(Assert { test, error });
+        Ok(true)
+    }
+
+    /// A `BinResult<()>` that is an error at `pos` unless the test holds.
+    pub(crate) fn check(&self, pos: &TokenStream) -> TokenStream {
+        let test = &self.test;
+        let error_fn = match &self.error {
+            AssertError::Default => {
+                let message = format!("assertion failed: `{}`", quote!(#test));
+                quote! {
+                    binrw::private::AssertErrorFn::<_, fn() -> !>::Message(|| #message)
+                }
+            }
+            AssertError::Message(format) => quote! {
+                binrw::private::AssertErrorFn::<_, fn() -> !>::Message(|| {
+                    std::format!(#(#format),*)
+                })
+            },
+            AssertError::Custom(error) => quote! {
+                binrw::private::AssertErrorFn::<fn() -> &'static str, _>::Error(|| #error)
+            },
+        };
+        quote_spanned! {test.span()=>
+            binrw::private::assert(#test, #pos, #error_fn)
+        }
+    }
+}
+
+/// Where a field sits in the stream, from `pad_before`, `pad_after`,
+/// `align_before`, `align_after`, `pad_size_to`, `pad_byte`, `seek_before`
+/// and `restore_position`.
+#[derive(Default)]
+pub(crate) struct Padding {
+    pub(crate) before: Option<Expr>,
+    pub(crate) after: Option<Expr>,
+    pub(crate) align_before: Option<Expr>,
+    pub(crate) align_after: Option<Expr>,
+    pub(crate) size: Option<Expr>,
+    pub(crate) fill: Option<Expr>,
+    pub(crate) seek: Option<Expr>,
+    pub(crate) restore: bool,
+}
+
+impl Padding {
+    pub(crate) fn set(&mut self, directive: &Directive) -> syn::Result<bool> {
+        if directive.is("restore_position") {
+            directive.flag()?;
+            if self.restore {
+                return Err(directive.error("duplicate `restore_position`"));
+            }
+            self.restore = true;
+            return Ok(true);
+        }
+        let slot = if directive.is("pad_before") {
+            &mut self.before
+        } else if directive.is("pad_after") {
+            &mut self.after
+        } else if directive.is("align_before") {
+            &mut self.align_before
+        } else if directive.is("align_after") {
+            &mut self.align_after
+        } else if directive.is("pad_size_to") {
+            &mut self.size
+        } else if directive.is("pad_byte") {
+            &mut self.fill
+        } else if directive.is("seek_before") {
+            &mut self.seek
+        } else {
+            return Ok(false);
+        };
+        if slot.is_some() {
+            return Err(directive.error(format_args!("duplicate `{}`", directive.name)));
+        }
+        *slot = Some(directive.expr()?);
+        Ok(true)
+    }
+
+    /// Whether the field moves through whole bytes before its value.
+    pub(crate) fn bytes_before(&self) -> bool {
+        self.before.is_some() || self.align_before.is_some() || self.seek.is_some() || self.restore
+    }
+
+    /// Whether the field moves through whole bytes after its value.
+    pub(crate) fn bytes_after(&self) -> bool {
+        self.after.is_some() || self.align_after.is_some() || self.size.is_some() || self.restore
+    }
+
+    /// Moves the stream to where the field starts, remembering the position
+    /// to return to or pad from.
+    pub(crate) fn before(&self, direction: Direction) -> TokenStream {
+        let stream = direction.stream();
+        let restore = self.restore.then(|| {
+            quote! {
+                let __binrw_restore = binrw::io::Seek::stream_position(#stream).await?;
+            }
+        });
+        let seek = self.seek.as_ref().map(|seek| {
+            quote! {
+                binrw::io::Seek::seek(#stream, #seek).await?;
+            }
+        });
+        let pad = (self.before.as_ref())
+            .map(|count| self.call(direction, "padding", quote! { (#count) as u64 }));
+        let align = (self.align_before.as_ref())
+            .map(|align| self.call(direction, "align", quote! { (#align) as u64 }));
+        let start = self.size.as_ref().map(|_| {
+            quote! {
+                let __binrw_start = binrw::io::Seek::stream_position(#stream).await?;
+            }
+        });
+        quote! { #restore #seek #pad #align #start }
+    }
+
+    /// Pads the field out after its value, then returns to the position
+    /// remembered for `restore_position`.
+    pub(crate) fn after(&self, direction: Direction) -> TokenStream {
+        let stream = direction.stream();
+        let size = self.size.as_ref().map(|size| {
+            self.call(
+                direction,
+                "pad_to",
+                quote! { __binrw_start, (#size) as u64 },
+            )
+        });
+        let pad = (self.after.as_ref())
+            .map(|count| self.call(direction, "padding", quote! { (#count) as u64 }));
+        let align = (self.align_after.as_ref())
+            .map(|align| self.call(direction, "align", quote! { (#align) as u64 }));
+        let restore = self.restore.then(|| {
+            quote! {
+                binrw::io::Seek::seek(#stream, binrw::io::SeekFrom::Start(__binrw_restore))
+                    .await?;
+            }
+        });
+        quote! { #size #pad #align #restore }
+    }
+
+    /// Calls the `binrw::padding` helper `read_{name}` or `write_{name}`,
+    /// adding the fill byte when writing.
+    fn call(&self, direction: Direction, name: &str, args: TokenStream) -> TokenStream {
+        let stream = direction.stream();
+        match direction {
+            Direction::Read => {
+                let helper = quote::format_ident!("read_{}", name);
+                quote! { binrw::padding::#helper(#stream, #args).await?; }
+            }
+            Direction::Write => {
+                let helper = quote::format_ident!("write_{}", name);
+                let fill = self
+                    .fill
+                    .as_ref()
+                    .map_or_else(|| quote! { 0 }, |fill| quote! { #fill });
+                quote! { binrw::padding::#helper(#stream, #args, #fill).await?; }
+            }
+        }
+    }
+}
+
+/// The width of a bit-packed field, from `bits = n` or `bits(n)`.
+pub(crate) struct Bits(pub(crate) Expr);
+
+impl Bits {
+    pub(crate) fn set(slot: &mut Option<Self>, directive: &Directive) -> syn::Result<bool> {
+        if !directive.is("bits") {
+            return Ok(false);
+        }
+        if slot.is_some() {
+            return Err(directive.error("duplicate `bits`"));
+        }
+        *slot = Some(Bits(directive.expr()?));
+        Ok(true)
+    }
+
+    /// Fails to compile unless `ty` can hold a field this wide.
+    pub(crate) fn check(&self, ty: &Type) -> TokenStream {
+        let width = &self.0;
+        quote_spanned! {width.span()=>
+            const {
+                assert!(
+                    #width <= <#ty as binrw::io::bits::BitValue>::BITS,
+                    "`bits` is wider than the field type",
+                );
+            }
+        }
+    }
+}
+
+/// The order of the bits in packed fields, from `bit_order = ...`.
+pub(crate) struct BitOrderSpec(Expr);
+
+impl BitOrderSpec {
+    pub(crate) fn set(slot: &mut Option<Self>, directive: &Directive) -> syn::Result<bool> {
+        if !directive.is("bit_order") {
+            return Ok(false);
+        }
+        if slot.is_some() {
+            return Err(directive.error("conflicting bit_order"));
+        }
+        *slot = Some(BitOrderSpec(directive.expr()?));
+        Ok(true)
+    }
+
+    /// The order for a scope, falling back to `inherited` when unset.
+    pub(crate) fn resolve(spec: Option<&Self>, inherited: &TokenStream) -> TokenStream {
+        spec.map_or_else(|| inherited.clone(), |BitOrderSpec(expr)| quote! { #expr })
+    }
+}
+
+/// How a field takes part in bit packing.
+pub(crate) struct BitSlot {
+    /// Whether the field has `bits`.
+    pub(crate) packed: bool,
+    /// Whether the field reads or writes whole bytes or seeks before its
+    /// value, with `magic` or a [`Padding`] directive.
+    pub(crate) bytes_before: bool,
+    /// Whether the field reads or writes whole bytes or seeks after its
+    /// value, with a [`Padding`] directive.
+    pub(crate) bytes_after: bool,
+}
+
+/// Where a `bits` field sits in its run of consecutive `bits` fields, which
+/// share bytes and end at a byte boundary.
+#[derive(Clone, Copy)]
+pub(crate) struct BitRun {
+    pub(crate) start: bool,
+    pub(crate) end: bool,
+}
+
+impl BitRun {
+    /// The run position of each field, or `None` for fields without `bits`.
+    pub(crate) fn split(slots: &[BitSlot]) -> Vec<Option<Self>> {
+        let joined = |prev: &BitSlot, next: &BitSlot| {
+            prev.packed && next.packed && !prev.bytes_after && !next.bytes_before
+        };
+        slots
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| {
+                slot.packed.then(|| BitRun {
+                    start: index == 0 || !joined(&slots[index - 1], slot),
+                    end: slots.get(index + 1).is_none_or(|next| !joined(slot, next)),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Builds a backtrace frame pointing at `span`.
+pub(crate) fn frame(message: String, span: Span) -> TokenStream {
+    quote_spanned! {span=>
+        binrw::error::BacktraceFrame::Full {
+            code: None,
+            message: #message.into(),
+            file: file!(),
+            line: line!(),
+        }
+    }
+}
+
+/// The local name used for a field.
+pub(crate) fn binding(field: &syn::Field, index: usize) -> Ident {
+    field
+        .ident
+        .clone()
+        .unwrap_or_else(|| quote::format_ident!("self_{}", index))
+}
+
+/// The name used for a field in diagnostics.
+pub(crate) fn display_name(field: &syn::Field, index: usize) -> String {
+    field
+        .ident
+        .as_ref()
+        .map_or_else(|| index.to_string(), ToString::to_string)
+}
@@ -0,0 +1,238 @@
+//! Code generation for `#[bitfield(...)]`.
+
+use crate::attrs::{BitOrderSpec, Directive};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::parse::{ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Expr, Fields, Token, Type};
+
+/// The arguments of the attribute: the integer type, then directives.
+struct Options {
+    repr: Type,
+    bit_order: Option<BitOrderSpec>,
+}
+
+impl Options {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let repr = input.parse()?;
+        let mut bit_order = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            for directive in Punctuated::<Directive, Token![,]>::parse_terminated(input)? {
+                if !BitOrderSpec::set(&mut bit_order, &directive)? {
+                    return Err(directive.unknown());
+                }
+            }
+        }
+        Ok(Self { repr, bit_order })
+    }
+}
+
+/// A member of the bitfield and the width from its `#[bits(n)]`.
+struct Member<'a> {
+    field: &'a syn::Field,
+    width: Expr,
+    /// Every attribute except `bits`, which go on the getter.
+    attrs: Vec<&'a Attribute>,
+}
+
+impl<'a> Member<'a> {
+    fn new(field: &'a syn::Field) -> syn::Result<Self> {
+        let mut width = None;
+        let mut attrs = Vec::new();
+        for attr in &field.attrs {
+            if !attr.path().is_ident("bits") {
+                attrs.push(attr);
+            } else if width.is_some() {
+                return Err(syn::Error::new_spanned(attr, "duplicate `bits`"));
+            } else {
+                width = Some(attr.parse_args()?);
+            }
+        }
+        let width = width.ok_or_else(|| {
+            syn::Error::new(field.span(), "bitfield members need a `#[bits(n)]` width")
+        })?;
+        Ok(Self {
+            field,
+            width,
+            attrs,
+        })
+    }
+}
+
+pub(crate) fn expand(args: TokenStream, input: &DeriveInput) -> syn::Result<TokenStream> {
+    let options = Options::parse.parse2(args)?;
+    let Data::Struct(syn::DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "bitfield can only be used on structs with named fields",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "bitfield structs cannot be generic",
+        ));
+    }
+    let members = fields
+        .named
+        .iter()
+        .map(Member::new)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let vis = &input.vis;
+    let attrs = &input.attrs;
+    let repr = &options.repr;
+    let bit_order = BitOrderSpec::resolve(
+        options.bit_order.as_ref(),
+        &quote! { binrw::io::BitOrder::MsbFirst },
+    );
+    let value_trait = quote! { binrw::io::bits::BitValue };
+
+    let mut offset = quote! { 0 };
+    let mut accessors = Vec::new();
+    let mut checks = Vec::new();
+    let mut debug_fields = Vec::new();
+    for member in &members {
+        let field = member.field;
+        let ident = field.ident.as_ref().unwrap();
+        let setter = format_ident!("set_{}", ident);
+        let field_vis = &field.vis;
+        let ty = &field.ty;
+        let width = &member.width;
+        let doc = &member.attrs;
+        let set_doc = format!(
+            "Sets [`{ident}`](Self::{ident}), failing if `value` does not fit in its bits."
+        );
+
+        accessors.push(quote_spanned! {field.span()=>
+            #(#doc)*
+            #field_vis fn #ident(&self) -> #ty {
+                const SHIFT: u32 = binrw::private::bitfield_shift(
+                    #bit_order,
+                    <#repr as #value_trait>::BITS,
+                    #offset,
+                    #width,
+                );
+                binrw::private::bitfield_get(&self.0, SHIFT, #width)
+            }
+
+            #[doc = #set_doc]
+            #field_vis fn #setter(
+                &mut self,
+                value: #ty,
+            ) -> core::result::Result<(), binrw::io::BitOverflow> {
+                const SHIFT: u32 = binrw::private::bitfield_shift(
+                    #bit_order,
+                    <#repr as #value_trait>::BITS,
+                    #offset,
+                    #width,
+                );
+                binrw::private::bitfield_set(&mut self.0, SHIFT, #width, &value)
+            }
+        });
+        let message = format!("`{ident}` is wider than its type");
+        checks.push(quote_spanned! {width.span()=>
+            assert!(#width <= <#ty as #value_trait>::BITS, #message);
+        });
+        let label = ident.to_string();
+        debug_fields.push(quote! { .field(#label, &self.#ident()) });
+        offset = quote! { #offset + #width };
+    }
+    let total_message = format!(
+        "the members of `{type_name}` are wider than `{}`",
+        quote!(#repr)
+    );
+
+    Ok(quote! {
+        #(#attrs)*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #vis struct #name(#repr);
+
+        const _: () = {
+            #(#checks)*
+            assert!(#offset <= <#repr as #value_trait>::BITS, #total_message);
+        };
+
+        impl #name {
+            /// Wraps a raw integer, keeping any bits outside the members.
+            #vis const fn from_bits(bits: #repr) -> Self {
+                Self(bits)
+            }
+
+            /// The raw integer, with every member in place.
+            #vis const fn into_bits(self) -> #repr {
+                self.0
+            }
+
+            #(#accessors)*
+        }
+
+        impl From<#repr> for #name {
+            fn from(bits: #repr) -> Self {
+                Self(bits)
+            }
+        }
+
+        impl From<#name> for #repr {
+            fn from(value: #name) -> Self {
+                value.0
+            }
+        }
+
+        impl core::fmt::Debug for #name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(#type_name)
+                    #(#debug_fields)*
+                    .finish()
+            }
+        }
+
+        #[automatically_derived]
+        impl binrw::BinRead for #name {
+            type Args<'a> = ();
+
+            fn read_options<__BinrwR: binrw::io::Read + binrw::io::Seek + Send>(
+                __binrw_reader: &mut __BinrwR,
+                __binrw_endian: binrw::Endian,
+                (): Self::Args<'_>,
+            ) -> impl core::future::Future<Output = binrw::BinResult<Self>> + Send
+            where
+                Self: Send,
+            {
+                let __binrw_read =
+                    <#repr as binrw::BinRead>::read_options(__binrw_reader, __binrw_endian, ());
+                async move { __binrw_read.await.map(Self) }
+            }
+        }
+
+        #[automatically_derived]
+        impl binrw::BinWrite for #name {
+            type Args<'a> = ();
+
+            fn write_options<__BinrwW: binrw::io::Write + binrw::io::Seek + Send>(
+                &self,
+                __binrw_writer: &mut __BinrwW,
+                __binrw_endian: binrw::Endian,
+                (): Self::Args<'_>,
+            ) -> impl core::future::Future<Output = binrw::BinResult<()>> + Send
+            where
+                Self: Sync,
+            {
+                <#repr as binrw::BinWrite>::write_options(
+                    &self.0,
+                    __binrw_writer,
+                    __binrw_endian,
+                    (),
+                )
+            }
+        }
+    })
+}
@@ -0,0 +1,79 @@
+//! Derive macros for `binrw`.
+
+mod attribute;
+mod attrs;
+mod bitfield;
+mod named_args;
+mod read;
+mod write;
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+/// Derives `BinRead` for a struct or enum, reading each field in declaration
+/// order.
+///
+/// Enum variants are tried in order until one of them parses. Fields and the
+/// type itself are configured with `#[br(...)]` and `#[brw(...)]` attributes.
+#[proc_macro_derive(BinRead, attributes(br, bw, brw))]
+pub fn derive_binread(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    attribute::reject_temp(&input)
+        .and_then(|()| read::derive(&input))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `BinWrite` for a struct or enum, writing each field in
+/// declaration order.
+///
+/// Fields and the type itself are configured with `#[bw(...)]` and
+/// `#[brw(...)]` attributes.
+#[proc_macro_derive(BinWrite, attributes(br, bw, brw))]
+pub fn derive_binwrite(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    attribute::reject_temp(&input)
+        .and_then(|()| write::derive(&input))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives both `BinRead` and `BinWrite`, and also allows `temp` fields.
+///
+/// A `#[br(temp)]` field is read like any other and can be used by the fields
+/// after it, but is left out of the type. Writing one needs a `#[bw(calc = ...)]`
+/// that recomputes it from the stored fields. Put `#[binrw]` above any other
+/// derives on the type.
+#[proc_macro_attribute]
+pub fn binrw(args: TokenStream, input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    attribute::expand(args.into(), &input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `NamedArgs` for a struct with named fields, generating a builder
+/// that checks at compile time that every required field has been set.
+///
+/// Fields marked `#[named_args(default = expr)]` may be left unset.
+#[proc_macro_derive(NamedArgs, attributes(named_args))]
+pub fn derive_named_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    named_args::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turns a struct of `#[bits(n)]` members into a newtype over an integer,
+/// with a getter, a checked `set_*` setter and a `Debug` entry per member.
+///
+/// `#[bitfield(u32)]` packs the members from the most significant bit down;
+/// `#[bitfield(u32, bit_order = BitOrder::LsbFirst)]` from bit 0 up. The type
+/// reads and writes as the integer, in whatever endianness it is given.
+#[proc_macro_attribute]
+pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    bitfield::expand(args.into(), &input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
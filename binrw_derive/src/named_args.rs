@@ -0,0 +1,211 @@
+//! Code generation for `#[derive(NamedArgs)]` and `import { ... }`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    Data, DeriveInput, Expr, Fields, GenericParam, Generics, Ident, Token, Type, Visibility,
+};
+
+/// A single named argument.
+pub(crate) struct NamedField {
+    pub(crate) name: Ident,
+    pub(crate) ty: Type,
+    pub(crate) default: Option<Expr>,
+}
+
+impl Parse for NamedField {
+    /// Parses `name: Type` or `name: Type = default`.
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        let default = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { name, ty, default })
+    }
+}
+
+pub(crate) fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(syn::DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "NamedArgs can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut named = Vec::new();
+    for field in &fields.named {
+        let mut default = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("named_args"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    default = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown named_args directive"))
+                }
+            })?;
+        }
+        named.push(NamedField {
+            name: field.ident.clone().unwrap(),
+            ty: field.ty.clone(),
+            default,
+        });
+    }
+
+    Ok(builder(&input.vis, &input.ident, &input.generics, &named))
+}
+
+/// Generates the typestate builder for the struct `ident`.
+///
+/// Each field gets a type parameter that is `Needed` until the field is set,
+/// so `finalize` only compiles once every required field has a value.
+pub(crate) fn builder(
+    vis: &Visibility,
+    ident: &Ident,
+    generics: &Generics,
+    fields: &[NamedField],
+) -> TokenStream {
+    let builder = format_ident!("{}Builder", ident);
+    let names = fields.iter().map(|field| &field.name).collect::<Vec<_>>();
+    let types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let states = (0..fields.len())
+        .map(|index| format_ident!("__BinrwArg{}", index))
+        .collect::<Vec<_>>();
+    let initial = fields
+        .iter()
+        .map(|field| match field.default {
+            Some(_) => quote! { binrw::private::Optional },
+            None => quote! { binrw::private::Needed },
+        })
+        .collect::<Vec<_>>();
+    let values = fields.iter().map(|field| {
+        let name = &field.name;
+        match &field.default {
+            Some(default) => quote! { #name: self.#name.unwrap_or_else(|| #default) },
+            None => quote! { #name: self.#name.unwrap() },
+        }
+    });
+
+    let struct_args = generic_args(generics);
+    let mut builder_generics = generics.clone();
+    builder_generics.params.extend(
+        states
+            .iter()
+            .map(|state| -> GenericParam { syn::parse_quote!(#state) }),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (builder_impl_generics, _, _) = builder_generics.split_for_impl();
+
+    let setters = fields.iter().enumerate().map(|(index, field)| {
+        let name = &field.name;
+        let ty = &field.ty;
+        let next = states.iter().enumerate().map(|(other, state)| {
+            if other == index {
+                quote! { binrw::private::Satisfied }
+            } else {
+                quote! { #state }
+            }
+        });
+        let others = names.iter().filter(|other| **other != name);
+        quote! {
+            #vis fn #name(self, #name: #ty) -> #builder<#(#struct_args,)* #(#next,)*> {
+                #builder {
+                    #name: Some(#name),
+                    #(#others: self.#others,)*
+                    __binrw_state: core::marker::PhantomData,
+                }
+            }
+        }
+    });
+
+    let default = fields.iter().all(|field| field.default.is_some()).then(|| {
+        quote! {
+            impl #impl_generics core::default::Default for #ident #ty_generics #where_clause {
+                fn default() -> Self {
+                    Self::builder().finalize()
+                }
+            }
+        }
+    });
+
+    let doc = format!("A builder for [`{ident}`].");
+
+    quote! {
+        #[doc = #doc]
+        #[must_use]
+        #vis struct #builder #builder_generics #where_clause {
+            #(#names: Option<#types>,)*
+            __binrw_state: core::marker::PhantomData<(#(#states,)*)>,
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Starts building a set of named arguments.
+            #vis fn builder() -> #builder<#(#struct_args,)* #(#initial,)*> {
+                #builder {
+                    #(#names: None,)*
+                    __binrw_state: core::marker::PhantomData,
+                }
+            }
+        }
+
+        impl #builder_impl_generics #builder<#(#struct_args,)* #(#states,)*> #where_clause {
+            #(#setters)*
+
+            /// Builds the arguments once every required one has been set.
+            #vis fn finalize(self) -> #ident #ty_generics
+            where
+                #(#states: binrw::private::SatisfiedOrOptional,)*
+            {
+                #ident {
+                    #(#values,)*
+                }
+            }
+        }
+
+        impl #impl_generics binrw::NamedArgs for #ident #ty_generics #where_clause {
+            type Builder = #builder<#(#struct_args,)* #(#initial,)*>;
+
+            fn builder() -> Self::Builder {
+                Self::builder()
+            }
+        }
+
+        #default
+    }
+}
+
+/// The generic arguments naming each parameter of `generics`, in order.
+fn generic_args(generics: &Generics) -> Vec<TokenStream> {
+    generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                quote! { #lifetime }
+            }
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                quote! { #ident }
+            }
+            GenericParam::Const(param) => {
+                let ident = &param.ident;
+                quote! { #ident }
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,555 @@
+//! Code generation for `#[derive(BinRead)]`.
+
+use crate::attrs::{
+    Assert, BitOrderSpec, BitRun, BitSlot, Bits, Condition, Direction, EndianSpec, FieldArgs,
+    Imports, Magic, Padding, Repr, Scope, UnitEnum, args_lifetime, binding, directives,
+    display_name, frame,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Fields};
+
+/// How a data enum reports that none of its variants could be read.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum ErrorMode {
+    /// Collect the error of every variant into `Error::EnumErrors`.
+    #[default]
+    AllErrors,
+    /// Stop at the first error that is not a magic or assertion mismatch.
+    UnexpectedError,
+}
+
+/// Options that apply to a whole struct or enum, or to a single variant.
+#[derive(Default)]
+struct TopLevel {
+    endian: Option<EndianSpec>,
+    imports: Imports,
+    magic: Option<Magic>,
+    repr: Option<Repr>,
+    bit_order: Option<BitOrderSpec>,
+    error_mode: Option<ErrorMode>,
+    /// Checks run once every field has been read.
+    asserts: Vec<Assert>,
+}
+
+impl TopLevel {
+    fn parse(attrs: &[syn::Attribute], scope: Scope) -> syn::Result<Self> {
+        let mut this = Self::default();
+        for directive in directives(attrs, Direction::Read)? {
+            if EndianSpec::set(&mut this.endian, &directive)?
+                || Magic::set(&mut this.magic, &directive)?
+                || BitOrderSpec::set(&mut this.bit_order, &directive)?
+                || (scope != Scope::Variant && this.imports.set(&directive)?)
+                || (scope == Scope::Enum && Repr::set(&mut this.repr, &directive)?)
+                || (scope != Scope::Enum && Assert::set(&mut this.asserts, &directive)?)
+            {
+                continue;
+            }
+            let mode = if directive.is("return_all_errors") {
+                ErrorMode::AllErrors
+            } else if directive.is("return_unexpected_error") {
+                ErrorMode::UnexpectedError
+            } else {
+                return Err(directive.unknown());
+            };
+            directive.flag()?;
+            if scope != Scope::Enum {
+                return Err(
+                    directive.error(format_args!("`{}` is only valid on enums", directive.name))
+                );
+            }
+            if this.error_mode.is_some() {
+                return Err(directive.error("conflicting enum error modes"));
+            }
+            this.error_mode = Some(mode);
+        }
+        Ok(this)
+    }
+}
+
+/// How the value of a field is produced.
+#[derive(Default)]
+enum Source {
+    #[default]
+    Read,
+    Default,
+    Map(Expr),
+    TryMap(Expr),
+    ParseWith(Expr),
+    Calc(Expr),
+}
+
+/// Options that apply to a single field.
+#[derive(Default)]
+struct FieldOptions {
+    endian: Option<EndianSpec>,
+    args: FieldArgs,
+    magic: Option<Magic>,
+    padding: Padding,
+    bits: Option<Bits>,
+    condition: Option<Condition>,
+    asserts: Vec<Assert>,
+    /// Whether the value is dropped once the fields after it are read.
+    temp: bool,
+    source: Source,
+}
+
+impl FieldOptions {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut this = Self::default();
+        for directive in directives(attrs, Direction::Read)? {
+            if EndianSpec::set(&mut this.endian, &directive)?
+                || this.args.set(&directive)?
+                || Magic::set(&mut this.magic, &directive)?
+                || this.padding.set(&directive)?
+                || Bits::set(&mut this.bits, &directive)?
+                || Condition::set(&mut this.condition, &directive)?
+                || Assert::set(&mut this.asserts, &directive)?
+            {
+                continue;
+            }
+            if directive.is("temp") {
+                directive.flag()?;
+                this.temp = true;
+                continue;
+            }
+            let source = if directive.is("ignore") || directive.is("default") {
+                directive.flag()?;
+                Source::Default
+            } else if directive.is("map") {
+                Source::Map(directive.expr()?)
+            } else if directive.is("try_map") {
+                Source::TryMap(directive.expr()?)
+            } else if directive.is("parse_with") {
+                Source::ParseWith(directive.expr()?)
+            } else if directive.is("calc") {
+                Source::Calc(directive.expr()?)
+            } else {
+                return Err(directive.unknown());
+            };
+            if !matches!(this.source, Source::Read) {
+                return Err(directive.error("conflicting field value directives"));
+            }
+            this.source = source;
+        }
+        if let Some(Bits(width)) = &this.bits
+            && !(matches!(this.source, Source::Read)
+                && matches!(this.args, FieldArgs::None)
+                && this.condition.is_none())
+        {
+            return Err(syn::Error::new_spanned(
+                width,
+                "`bits` fields are read as plain integers and cannot have args, `if` or another value directive",
+            ));
+        }
+        Ok(this)
+    }
+
+    fn bit_slot(&self) -> BitSlot {
+        BitSlot {
+            packed: self.bits.is_some(),
+            bytes_before: self.padding.bytes_before() || self.magic.is_some(),
+            bytes_after: self.padding.bytes_after(),
+        }
+    }
+}
+
+pub(crate) fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let type_name = input.ident.to_string();
+    let (top, body) = match &input.data {
+        Data::Struct(data) => {
+            let top = TopLevel::parse(&input.attrs, Scope::Struct)?;
+            let body = read_struct(&type_name, &top, &data.fields)?;
+            (top, body)
+        }
+        Data::Enum(data) => {
+            let top = TopLevel::parse(&input.attrs, Scope::Enum)?;
+            let body = match UnitEnum::new(input, data, top.repr.as_ref(), Direction::Read)? {
+                Some(unit) => read_unit_enum(&top, &unit),
+                None => read_enum(&type_name, &top, data)?,
+            };
+            (top, body)
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "BinRead cannot be derived for unions",
+            ));
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let args_lifetime = args_lifetime();
+    let args_type = top.imports.args_type(input, Direction::Read);
+    let definition = top.imports.definition(input, Direction::Read);
+
+    Ok(quote! {
+        #definition
+
+        #[automatically_derived]
+        impl #impl_generics binrw::BinRead for #name #ty_generics #where_clause {
+            type Args<#args_lifetime> = #args_type;
+
+            fn read_options<__BinrwR: binrw::io::Read + binrw::io::Seek + Send>(
+                __binrw_reader: &mut __BinrwR,
+                __binrw_endian: binrw::Endian,
+                __binrw_args: Self::Args<'_>,
+            ) -> impl core::future::Future<Output = binrw::BinResult<Self>> + Send
+            where
+                Self: Send,
+            {
+                async move {
+                    let __binrw_pos = binrw::io::Seek::stream_position(__binrw_reader).await?;
+                    let __binrw_result: binrw::BinResult<Self> = async { #body }.await;
+                    match __binrw_result {
+                        Ok(__binrw_value) => Ok(__binrw_value),
+                        Err(__binrw_err) => Err(binrw::private::restore_position(
+                            __binrw_reader,
+                            __binrw_pos,
+                        )
+                        .await(__binrw_err)),
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Reads a struct body, leaving the reader wherever parsing stopped.
+fn read_struct(type_name: &str, top: &TopLevel, fields: &Fields) -> syn::Result<TokenStream> {
+    let endian = EndianSpec::resolve(top.endian.as_ref(), &quote! { __binrw_endian });
+    let imports = top.imports.bind();
+    let magic = top.magic.as_ref().map(|magic| {
+        let check = magic.read(&quote! { __binrw_endian });
+        quote! { #check?; }
+    });
+    let bit_order = BitOrderSpec::resolve(top.bit_order.as_ref(), &default_bit_order());
+    let asserts = top
+        .asserts
+        .iter()
+        .map(|assert| assert.check(&quote! { __binrw_pos }));
+    let fields = read_fields(
+        type_name,
+        fields,
+        &quote! { Self },
+        &bit_order,
+        quote! { #(#asserts?;)* },
+    )?;
+
+    Ok(quote! {
+        let __binrw_endian: binrw::Endian = #endian;
+        #imports
+        #magic
+        #fields
+    })
+}
+
+/// Reads the discriminant of a fieldless enum and picks the matching variant.
+fn read_unit_enum(top: &TopLevel, unit: &UnitEnum<'_>) -> TokenStream {
+    let endian = EndianSpec::resolve(top.endian.as_ref(), &quote! { __binrw_endian });
+    let imports = top.imports.bind();
+    let magic = top.magic.as_ref().map(|magic| {
+        let check = magic.read(&quote! { __binrw_endian });
+        quote! { #check?; }
+    });
+    let repr = &unit.repr;
+    let matches = unit.variants.iter().map(|(ident, discriminant)| {
+        quote! {
+            if __binrw_value == #discriminant {
+                return Ok(Self::#ident);
+            }
+        }
+    });
+    let unknown = match unit.fallback {
+        Some(fallback) => quote! { Ok(Self::#fallback(__binrw_value)) },
+        None => quote! { Err(binrw::Error::NoVariantMatch { pos: __binrw_variant_pos }) },
+    };
+
+    quote! {
+        let __binrw_endian: binrw::Endian = #endian;
+        #imports
+        #magic
+        let __binrw_variant_pos = binrw::io::Seek::stream_position(__binrw_reader).await?;
+        let __binrw_value =
+            <#repr as binrw::BinRead>::read_options(__binrw_reader, __binrw_endian, ()).await?;
+        #(#matches)*
+        #unknown
+    }
+}
+
+/// Tries each variant of a data enum in order, rewinding between attempts.
+fn read_enum(type_name: &str, top: &TopLevel, data: &syn::DataEnum) -> syn::Result<TokenStream> {
+    let endian = EndianSpec::resolve(top.endian.as_ref(), &quote! { __binrw_endian });
+    let imports = top.imports.bind();
+    let magic = top.magic.as_ref().map(|magic| {
+        let check = magic.read(&quote! { __binrw_endian });
+        quote! { #check?; }
+    });
+    let mode = top.error_mode.unwrap_or_default();
+    let top_bit_order = BitOrderSpec::resolve(top.bit_order.as_ref(), &default_bit_order());
+
+    let mut attempts = Vec::new();
+    for variant in &data.variants {
+        let options = TopLevel::parse(&variant.attrs, Scope::Variant)?;
+        let ident = &variant.ident;
+        let variant_name = ident.to_string();
+        let endian = EndianSpec::resolve(options.endian.as_ref(), &quote! { __binrw_endian });
+        let magic = options.magic.as_ref().map(|magic| {
+            let check = magic.read(&quote! { __binrw_endian });
+            quote! { #check?; }
+        });
+        let bit_order = BitOrderSpec::resolve(options.bit_order.as_ref(), &top_bit_order);
+        let asserts = options
+            .asserts
+            .iter()
+            .map(|assert| assert.check(&quote! { __binrw_variant_pos }));
+        let fields = read_fields(
+            &format!("{type_name}::{variant_name}"),
+            &variant.fields,
+            &quote! { Self::#ident },
+            &bit_order,
+            quote! { #(#asserts?;)* },
+        )?;
+        let on_error = match mode {
+            ErrorMode::AllErrors => quote! {
+                __binrw_errors.push((#variant_name, __binrw_err));
+            },
+            ErrorMode::UnexpectedError => quote! {
+                if !matches!(
+                    __binrw_err.root_cause(),
+                    binrw::Error::BadMagic { .. } | binrw::Error::AssertFail { .. }
+                ) {
+                    return Err(__binrw_err);
+                }
+            },
+        };
+
+        attempts.push(quote! {
+            let __binrw_attempt: binrw::BinResult<Self> = async {
+                let __binrw_endian: binrw::Endian = #endian;
+                #magic
+                #fields
+            }
+            .await;
+            match __binrw_attempt {
+                Ok(__binrw_value) => return Ok(__binrw_value),
+                Err(__binrw_err) => {
+                    let __binrw_err = binrw::private::restore_position_variant(
+                        __binrw_reader,
+                        __binrw_variant_pos,
+                        __binrw_err,
+                    )
+                    .await?;
+                    #on_error
+                }
+            }
+        });
+    }
+
+    let (errors, no_match) = match mode {
+        ErrorMode::AllErrors => (
+            Some(quote! { let mut __binrw_errors = Vec::new(); }),
+            quote! {
+                binrw::Error::EnumErrors {
+                    pos: __binrw_variant_pos,
+                    variant_errors: __binrw_errors,
+                }
+            },
+        ),
+        ErrorMode::UnexpectedError => (
+            None,
+            quote! { binrw::Error::NoVariantMatch { pos: __binrw_variant_pos } },
+        ),
+    };
+
+    Ok(quote! {
+        let __binrw_endian: binrw::Endian = #endian;
+        #imports
+        #magic
+        let __binrw_variant_pos = binrw::io::Seek::stream_position(__binrw_reader).await?;
+        #errors
+        #(#attempts)*
+        Err(#no_match)
+    })
+}
+
+/// The bit order of `bits` fields when the type does not set one.
+fn default_bit_order() -> TokenStream {
+    quote! { binrw::io::BitOrder::MsbFirst }
+}
+
+/// Reads every field in order, runs `asserts` and builds the value with
+/// `ctor`.
+fn read_fields(
+    type_name: &str,
+    fields: &Fields,
+    ctor: &TokenStream,
+    bit_order: &TokenStream,
+    asserts: TokenStream,
+) -> syn::Result<TokenStream> {
+    let options = fields
+        .iter()
+        .map(|field| FieldOptions::parse(&field.attrs))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let runs = BitRun::split(
+        &options
+            .iter()
+            .map(FieldOptions::bit_slot)
+            .collect::<Vec<_>>(),
+    );
+    let mut reads = Vec::new();
+    let mut bindings = Vec::new();
+    for (index, (field, options)) in fields.iter().zip(&options).enumerate() {
+        let name = binding(field, index);
+        let bits = runs[index].map(|run| (run, bit_order));
+        reads.push(read_field(type_name, field, index, &name, options, bits));
+        if !options.temp {
+            bindings.push(name);
+        }
+    }
+
+    let value = match fields {
+        Fields::Named(_) => quote! { #ctor { #(#bindings),* } },
+        Fields::Unnamed(_) => quote! { #ctor(#(#bindings),*) },
+        Fields::Unit => quote! { #ctor },
+    };
+
+    Ok(quote! {
+        #(#reads)*
+        #asserts
+        Ok(#value)
+    })
+}
+
+fn read_field(
+    type_name: &str,
+    field: &syn::Field,
+    index: usize,
+    name: &syn::Ident,
+    options: &FieldOptions,
+    bits: Option<(BitRun, &TokenStream)>,
+) -> TokenStream {
+    let ty = &field.ty;
+    let span = field.span();
+    let context = frame(
+        format!(
+            "While parsing field '{}' in {type_name}",
+            display_name(field, index)
+        ),
+        span,
+    );
+    let map_err = quote_spanned! {span=>
+        .map_err(|__binrw_err| binrw::error::ContextExt::with_context(__binrw_err, #context))
+    };
+    let endian = EndianSpec::resolve(options.endian.as_ref(), &quote! { __binrw_endian });
+    let field_args = options.args.tokens(Some(ty), Direction::Read);
+    let args = options.args.tokens(None, Direction::Read);
+
+    let magic = options.magic.as_ref().map(|magic| {
+        let check = magic.read(&endian);
+        quote! { #check #map_err?; }
+    });
+
+    let mut start_bits = None;
+    let value = match &options.source {
+        Source::Read if let (Some(spec), Some((run, bit_order))) = (&options.bits, bits) => {
+            let Bits(width) = spec;
+            let check = spec.check(ty);
+            if run.start {
+                start_bits = Some(quote! {
+                    let mut __binrw_bits = binrw::io::bits::BitUnpacker::new(#bit_order);
+                });
+            }
+            quote_spanned! {span=>
+                {
+                    #check
+                    binrw::private::read_bits::<_, #ty>(
+                        __binrw_reader,
+                        &mut __binrw_bits,
+                        #width,
+                    )
+                    .await
+                    #map_err?
+                }
+            }
+        }
+        Source::Default => quote! { <#ty as core::default::Default>::default() },
+        Source::Calc(calc) => quote_spanned! {span=> #calc },
+        Source::Read => quote_spanned! {span=>
+            <#ty as binrw::BinRead>::read_options(__binrw_reader, #endian, #field_args)
+                .await
+                #map_err?
+        },
+        Source::Map(map) => quote_spanned! {span=>
+            binrw::private::read_map(
+                __binrw_reader,
+                #endian,
+                binrw::private::coerce_fn::<#ty, _, _>(#map),
+                #args,
+            )
+            .await
+            #map_err?
+        },
+        Source::TryMap(map) => quote_spanned! {span=>
+            binrw::private::read_try_map(
+                __binrw_reader,
+                #endian,
+                binrw::private::coerce_fn::<core::result::Result<#ty, _>, _, _>(#map),
+                #args,
+            )
+            .await
+            #map_err?
+        },
+        Source::ParseWith(parser) => quote_spanned! {span=>
+            binrw::private::parse_fn_type_hint(#parser)(__binrw_reader, #endian, #args)
+                .await
+                #map_err?
+        },
+    };
+
+    let pad_before = options.padding.before(Direction::Read);
+    let pad_after = options.padding.after(Direction::Read);
+    let (value_pos, asserts) = if options.asserts.is_empty() {
+        (None, None)
+    } else {
+        let pos = quote! { __binrw_field_pos };
+        let asserts = options.asserts.iter().map(|assert| assert.check(&pos));
+        (
+            Some(quote! {
+                let #pos = binrw::io::Seek::stream_position(__binrw_reader).await?;
+            }),
+            Some(quote! { #(#asserts #map_err?;)* }),
+        )
+    };
+
+    let Some(Condition { test, alternate }) = &options.condition else {
+        return quote! {
+            #pad_before
+            #magic
+            #start_bits
+            #value_pos
+            let #name: #ty = #value;
+            #asserts
+            #pad_after
+        };
+    };
+    let alternate = alternate.as_ref().map_or_else(
+        || quote! { <#ty as core::default::Default>::default() },
+        |alternate| quote! { #alternate },
+    );
+    quote! {
+        let #name: #ty = if #test {
+            #pad_before
+            #magic
+            #value_pos
+            let #name: #ty = #value;
+            #asserts
+            #pad_after
+            #name
+        } else {
+            #alternate
+        };
+    }
+}
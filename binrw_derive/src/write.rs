@@ -0,0 +1,448 @@
+//! Code generation for `#[derive(BinWrite)]`.
+
+use crate::attrs::{
+    BitOrderSpec, BitRun, BitSlot, Bits, Condition, Direction, EndianSpec, FieldArgs, Imports,
+    Magic, Padding, Repr, Scope, UnitEnum, args_lifetime, binding, directives, display_name, frame,
+    is_temp,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Fields};
+
+/// Options that apply to a whole struct or enum, or to a single variant.
+#[derive(Default)]
+struct TopLevel {
+    endian: Option<EndianSpec>,
+    imports: Imports,
+    magic: Option<Magic>,
+    repr: Option<Repr>,
+    bit_order: Option<BitOrderSpec>,
+}
+
+impl TopLevel {
+    fn parse(attrs: &[syn::Attribute], scope: Scope) -> syn::Result<Self> {
+        let mut this = Self::default();
+        for directive in directives(attrs, Direction::Write)? {
+            if directive.is_read_only() {
+                continue;
+            }
+            if !(EndianSpec::set(&mut this.endian, &directive)?
+                || Magic::set(&mut this.magic, &directive)?
+                || BitOrderSpec::set(&mut this.bit_order, &directive)?
+                || (scope != Scope::Variant && this.imports.set(&directive)?)
+                || (scope == Scope::Enum && Repr::set(&mut this.repr, &directive)?))
+            {
+                return Err(directive.unknown());
+            }
+        }
+        Ok(this)
+    }
+}
+
+/// How a field is turned into output.
+#[derive(Default)]
+enum Sink {
+    #[default]
+    Write,
+    Ignore,
+    Map(Expr),
+    TryMap(Expr),
+    WriteWith(Expr),
+    Calc(Expr),
+}
+
+/// Options that apply to a single field.
+#[derive(Default)]
+struct FieldOptions {
+    endian: Option<EndianSpec>,
+    args: FieldArgs,
+    magic: Option<Magic>,
+    padding: Padding,
+    bits: Option<Bits>,
+    condition: Option<Condition>,
+    /// Whether the field is missing from the type, from `temp`.
+    temp: bool,
+    sink: Sink,
+}
+
+impl FieldOptions {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut this = Self {
+            temp: is_temp(field)?,
+            ..Self::default()
+        };
+        for directive in directives(&field.attrs, Direction::Write)? {
+            if directive.is_read_only() {
+                // `temp` is handled by `is_temp`, since it is usually on `br`.
+                continue;
+            }
+            if EndianSpec::set(&mut this.endian, &directive)?
+                || this.args.set(&directive)?
+                || Magic::set(&mut this.magic, &directive)?
+                || this.padding.set(&directive)?
+                || Bits::set(&mut this.bits, &directive)?
+                || Condition::set(&mut this.condition, &directive)?
+            {
+                continue;
+            }
+            let sink = if directive.is("ignore") {
+                directive.flag()?;
+                Sink::Ignore
+            } else if directive.is("map") {
+                Sink::Map(directive.expr()?)
+            } else if directive.is("try_map") {
+                Sink::TryMap(directive.expr()?)
+            } else if directive.is("write_with") {
+                Sink::WriteWith(directive.expr()?)
+            } else if directive.is("calc") {
+                Sink::Calc(directive.expr()?)
+            } else {
+                return Err(directive.unknown());
+            };
+            if !matches!(this.sink, Sink::Write) {
+                return Err(directive.error("conflicting field output directives"));
+            }
+            this.sink = sink;
+        }
+        if let Some(Bits(width)) = &this.bits
+            && !(matches!(this.sink, Sink::Write)
+                && matches!(this.args, FieldArgs::None)
+                && this.condition.is_none())
+        {
+            return Err(syn::Error::new_spanned(
+                width,
+                "`bits` fields are written as plain integers and cannot have args, `if` or another output directive",
+            ));
+        }
+        if this.temp && !matches!(this.sink, Sink::Calc(_) | Sink::Ignore) {
+            return Err(syn::Error::new(
+                field.span(),
+                "`temp` fields are not stored in the type, so they need `calc` or `ignore` to be written",
+            ));
+        }
+        Ok(this)
+    }
+
+    fn bit_slot(&self) -> BitSlot {
+        BitSlot {
+            packed: self.bits.is_some(),
+            bytes_before: self.padding.bytes_before() || self.magic.is_some(),
+            bytes_after: self.padding.bytes_after(),
+        }
+    }
+}
+
+pub(crate) fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let scope = match input.data {
+        Data::Enum(_) => Scope::Enum,
+        _ => Scope::Struct,
+    };
+    let top = TopLevel::parse(&input.attrs, scope)?;
+    let type_name = input.ident.to_string();
+    let endian = EndianSpec::resolve(top.endian.as_ref(), &quote! { __binrw_endian });
+    let magic = write_magic(top.magic.as_ref());
+    let bit_order = BitOrderSpec::resolve(
+        top.bit_order.as_ref(),
+        &quote! { binrw::io::BitOrder::MsbFirst },
+    );
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, fields) =
+                write_fields(&type_name, &data.fields, &quote! { Self }, &bit_order)?;
+            quote! {
+                #[allow(unused_variables)]
+                let #pattern = self;
+                #fields
+            }
+        }
+        Data::Enum(data) => {
+            match UnitEnum::new(input, data, top.repr.as_ref(), Direction::Write)? {
+                Some(unit) => write_unit_enum(&unit),
+                None => write_enum(&type_name, data, &bit_order)?,
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "BinWrite cannot be derived for unions",
+            ));
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let args_lifetime = args_lifetime();
+    let args_type = top.imports.args_type(input, Direction::Write);
+    let definition = top.imports.definition(input, Direction::Write);
+    let imports = top.imports.bind();
+
+    Ok(quote! {
+        #definition
+
+        #[automatically_derived]
+        impl #impl_generics binrw::BinWrite for #name #ty_generics #where_clause {
+            type Args<#args_lifetime> = #args_type;
+
+            fn write_options<__BinrwW: binrw::io::Write + binrw::io::Seek + Send>(
+                &self,
+                __binrw_writer: &mut __BinrwW,
+                __binrw_endian: binrw::Endian,
+                __binrw_args: Self::Args<'_>,
+            ) -> impl core::future::Future<Output = binrw::BinResult<()>> + Send
+            where
+                Self: Sync,
+            {
+                async move {
+                    let __binrw_endian: binrw::Endian = #endian;
+                    #imports
+                    #magic
+                    #body
+                    Ok(())
+                }
+            }
+        }
+    })
+}
+
+/// Writes the discriminant of a fieldless enum.
+fn write_unit_enum(unit: &UnitEnum<'_>) -> TokenStream {
+    let repr = &unit.repr;
+    let arms = unit.variants.iter().map(|(ident, discriminant)| {
+        quote! { Self::#ident => #discriminant, }
+    });
+    let fallback = unit.fallback.map(|fallback| {
+        quote! { Self::#fallback(__binrw_value) => *__binrw_value, }
+    });
+
+    quote! {
+        let __binrw_value: #repr = match self {
+            #(#arms)*
+            #fallback
+        };
+        <#repr as binrw::BinWrite>::write_options(
+            &__binrw_value,
+            __binrw_writer,
+            __binrw_endian,
+            (),
+        )
+        .await?;
+    }
+}
+
+/// Writes the fields of whichever variant of a data enum is present.
+fn write_enum(
+    type_name: &str,
+    data: &syn::DataEnum,
+    bit_order: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let options = TopLevel::parse(&variant.attrs, Scope::Variant)?;
+        let ident = &variant.ident;
+        let endian = EndianSpec::resolve(options.endian.as_ref(), &quote! { __binrw_endian });
+        let magic = write_magic(options.magic.as_ref());
+        let bit_order = BitOrderSpec::resolve(options.bit_order.as_ref(), bit_order);
+        let (pattern, fields) = write_fields(
+            &format!("{type_name}::{ident}"),
+            &variant.fields,
+            &quote! { Self::#ident },
+            &bit_order,
+        )?;
+        arms.push(quote! {
+            #[allow(unused_variables)]
+            #pattern => {
+                let __binrw_endian: binrw::Endian = #endian;
+                #magic
+                #fields
+            }
+        });
+    }
+
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+fn write_magic(magic: Option<&Magic>) -> Option<TokenStream> {
+    magic.map(|magic| {
+        let write = magic.write(&quote! { __binrw_endian });
+        quote! { #write?; }
+    })
+}
+
+/// Returns a pattern binding every field by reference, and the statements that
+/// write them in order.
+fn write_fields(
+    type_name: &str,
+    fields: &Fields,
+    ctor: &TokenStream,
+    bit_order: &TokenStream,
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let options = fields
+        .iter()
+        .map(FieldOptions::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let runs = BitRun::split(
+        &options
+            .iter()
+            .map(FieldOptions::bit_slot)
+            .collect::<Vec<_>>(),
+    );
+    let mut writes = Vec::new();
+    let mut bindings = Vec::new();
+    for (index, (field, options)) in fields.iter().zip(&options).enumerate() {
+        let name = binding(field, index);
+        let bits = runs[index].map(|run| (run, bit_order));
+        writes.push(write_field(type_name, field, index, &name, options, bits));
+        if !options.temp {
+            bindings.push(name);
+        }
+    }
+
+    let pattern = match fields {
+        Fields::Named(_) => quote! { #ctor { #(#bindings),* } },
+        Fields::Unnamed(_) => quote! { #ctor(#(#bindings),*) },
+        Fields::Unit => quote! { #ctor },
+    };
+
+    Ok((pattern, quote! { #(#writes)* }))
+}
+
+fn write_field(
+    type_name: &str,
+    field: &syn::Field,
+    index: usize,
+    name: &syn::Ident,
+    options: &FieldOptions,
+    bits: Option<(BitRun, &TokenStream)>,
+) -> TokenStream {
+    let ty = &field.ty;
+    let span = field.span();
+    let context = frame(
+        format!(
+            "While writing field '{}' in {type_name}",
+            display_name(field, index)
+        ),
+        span,
+    );
+    let map_err = quote_spanned! {span=>
+        .map_err(|__binrw_err| binrw::error::ContextExt::with_context(__binrw_err, #context))
+    };
+    let endian = EndianSpec::resolve(options.endian.as_ref(), &quote! { __binrw_endian });
+    let field_args = options.args.tokens(Some(ty), Direction::Write);
+    let args = options.args.tokens(None, Direction::Write);
+
+    let magic = options.magic.as_ref().map(|magic| {
+        let write = magic.write(&endian);
+        quote! { #write #map_err?; }
+    });
+    let pad_before = options.padding.before(Direction::Write);
+    let pad_after = options.padding.after(Direction::Write);
+
+    let write = match &options.sink {
+        Sink::Write if let (Some(spec), Some((run, bit_order))) = (&options.bits, bits) => {
+            let Bits(width) = spec;
+            let check = spec.check(ty);
+            let start = run.start.then(|| {
+                quote! {
+                    let mut __binrw_bits = binrw::io::bits::BitPacker::new(#bit_order);
+                }
+            });
+            let end = run.end.then(|| {
+                quote! {
+                    binrw::private::align_bits(__binrw_writer, &mut __binrw_bits)
+                        .await
+                        #map_err?;
+                }
+            });
+            return quote_spanned! {span=>
+                #pad_before
+                #magic
+                #start
+                #check
+                binrw::private::write_bits(
+                    __binrw_writer,
+                    &mut __binrw_bits,
+                    #name,
+                    #width,
+                )
+                .await
+                #map_err?;
+                #end
+                #pad_after
+            };
+        }
+        Sink::Ignore => None,
+        Sink::Write => Some(quote_spanned! {span=>
+            <#ty as binrw::BinWrite>::write_options(#name, __binrw_writer, #endian, #field_args)
+                .await
+                #map_err?;
+        }),
+        Sink::Map(map) => Some(quote_spanned! {span=>
+            let __binrw_map = #map;
+            #[allow(clippy::let_unit_value)]
+            let __binrw_args = binrw::private::write_map_args_type_hint(&__binrw_map, #args);
+            let __binrw_value = __binrw_map(#name);
+            binrw::BinWrite::write_options(
+                &__binrw_value,
+                __binrw_writer,
+                #endian,
+                __binrw_args,
+            )
+            .await
+            #map_err?;
+        }),
+        Sink::TryMap(map) => Some(quote_spanned! {span=>
+            let __binrw_map = #map;
+            #[allow(clippy::let_unit_value)]
+            let __binrw_args = binrw::private::write_try_map_args_type_hint(&__binrw_map, #args);
+            let __binrw_pos = binrw::io::Seek::stream_position(__binrw_writer).await?;
+            let __binrw_value = __binrw_map(#name)
+                .map_err(|__binrw_err| binrw::private::custom_error(__binrw_pos, __binrw_err))
+                #map_err?;
+            binrw::BinWrite::write_options(
+                &__binrw_value,
+                __binrw_writer,
+                #endian,
+                __binrw_args,
+            )
+            .await
+            #map_err?;
+        }),
+        Sink::Calc(_) => Some(quote_spanned! {span=>
+            <#ty as binrw::BinWrite>::write_options(#name, __binrw_writer, #endian, #field_args)
+                .await
+                #map_err?;
+        }),
+        Sink::WriteWith(writer) => Some(quote_spanned! {span=>
+            binrw::private::write_fn_type_hint(#writer)(#name, __binrw_writer, #endian, #args)
+                .await
+                #map_err?;
+        }),
+    };
+
+    // The calculated value replaces the field for the rest of the type, so
+    // later fields can use it even when the field itself is `temp`.
+    let calc = match &options.sink {
+        Sink::Calc(calc) => Some(quote_spanned! {span=> let #name: &#ty = &(#calc); }),
+        _ => None,
+    };
+    let field = quote! {
+        #pad_before
+        #magic
+        { #write }
+        #pad_after
+    };
+    let field = match &options.condition {
+        Some(Condition { test, .. }) => quote! { if #test { #field } },
+        None => field,
+    };
+    quote! {
+        #calc
+        #field
+    }
+}
@@ -0,0 +1,217 @@
+//! Blocking entry points for code without an async runtime.
+//!
+//! [`BinReadSync`] and [`BinWriteSync`] accept any [`std::io::Read`] or
+//! [`std::io::Write`] that also implements [`std::io::Seek`], wrap it in
+//! [`SyncIo`] and drive the read or write to completion on the current thread.
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+use crate::{BinRead, BinResult, BinWrite, Endian, Required};
+use std::io::SeekFrom;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
+
+/// Runs a future to completion on the current thread.
+///
+/// Futures over [`SyncIo`] and the in-memory and `File` streams never
+/// suspend, so this returns after a single poll; anything else parks the
+/// thread until it is woken.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// Adapts a blocking `std::io` stream to this crate's [`Read`], [`Write`]
+/// and [`Seek`] traits.
+pub struct SyncIo<T>(pub T);
+
+impl<T: std::io::Read + Send> Read for SyncIo<T> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move { self.0.read(buf) }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move { self.0.read_exact(buf) }
+    }
+}
+
+impl<T: std::io::Write + Send> Write for SyncIo<T> {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move { self.0.write(buf) }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move { self.0.flush() }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move { self.0.write_all(buf) }
+    }
+}
+
+impl<T: std::io::Seek + Send> Seek for SyncIo<T> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move { self.0.seek(pos) }
+    }
+}
+
+/// Blocking versions of the [`BinRead`] entry points.
+pub trait BinReadSync: BinRead + Send {
+    fn read_blocking<R>(reader: &mut R) -> BinResult<Self>
+    where
+        R: std::io::Read + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        Self::read_args_blocking(reader, Endian::Little, Self::Args::args())
+    }
+
+    fn read_be_blocking<R>(reader: &mut R) -> BinResult<Self>
+    where
+        R: std::io::Read + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        Self::read_args_blocking(reader, Endian::Big, Self::Args::args())
+    }
+
+    fn read_le_blocking<R>(reader: &mut R) -> BinResult<Self>
+    where
+        R: std::io::Read + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        Self::read_args_blocking(reader, Endian::Little, Self::Args::args())
+    }
+
+    fn read_ne_blocking<R>(reader: &mut R) -> BinResult<Self>
+    where
+        R: std::io::Read + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        Self::read_args_blocking(reader, Endian::NATIVE, Self::Args::args())
+    }
+
+    fn read_args_blocking<R>(
+        reader: &mut R,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<Self>
+    where
+        R: std::io::Read + std::io::Seek + Send,
+    {
+        block_on(Self::read_options(&mut SyncIo(reader), endian, args))
+    }
+}
+
+impl<T: BinRead + Send> BinReadSync for T {}
+
+/// Blocking versions of the [`BinWrite`] entry points.
+pub trait BinWriteSync: BinWrite + Sync {
+    fn write_blocking<W>(&self, writer: &mut W) -> BinResult<()>
+    where
+        W: std::io::Write + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        self.write_args_blocking(writer, Endian::Little, Self::Args::args())
+    }
+
+    fn write_be_blocking<W>(&self, writer: &mut W) -> BinResult<()>
+    where
+        W: std::io::Write + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        self.write_args_blocking(writer, Endian::Big, Self::Args::args())
+    }
+
+    fn write_le_blocking<W>(&self, writer: &mut W) -> BinResult<()>
+    where
+        W: std::io::Write + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        self.write_args_blocking(writer, Endian::Little, Self::Args::args())
+    }
+
+    fn write_ne_blocking<W>(&self, writer: &mut W) -> BinResult<()>
+    where
+        W: std::io::Write + std::io::Seek + Send,
+        for<'a> Self::Args<'a>: Required,
+    {
+        self.write_args_blocking(writer, Endian::NATIVE, Self::Args::args())
+    }
+
+    fn write_args_blocking<W>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<()>
+    where
+        W: std::io::Write + std::io::Seek + Send,
+    {
+        block_on(self.write_options(&mut SyncIo(writer), endian, args))
+    }
+}
+
+impl<T: BinWrite + Sync> BinWriteSync for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NullString;
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(magic = 0x7fu8)]
+    struct Entry {
+        id: u16,
+        name: NullString,
+    }
+
+    #[test]
+    fn test_blocking_round_trip() -> Result<()> {
+        let entry = Entry {
+            id: 0x102,
+            name: "a".into(),
+        };
+        let mut data = Cursor::new(Vec::new());
+        entry.write_be_blocking(&mut data)?;
+        assert_eq!(data.get_ref(), &[0x7f, 1, 2, b'a', 0]);
+
+        data.set_position(0);
+        assert_eq!(Entry::read_be_blocking(&mut data)?, entry);
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocking_file() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("binrw-blocking-{}", std::process::id()));
+        let mut file = std::fs::File::create(&path)?;
+        0xdead_beef_u32.write_le_blocking(&mut file)?;
+        drop(file);
+        let mut file = std::fs::File::open(&path)?;
+        let value = u32::read_le_blocking(&mut file);
+        std::fs::remove_file(&path)?;
+        assert_eq!(value?, 0xdead_beef);
+        Ok(())
+    }
+}
@@ -32,7 +32,8 @@ impl Endian {
             REVERSE_BOM => Ok(Self::Big),
             _ => Err(crate::Error::BadMagic {
                 pos: u64::MAX,
-                found: Box::new("Invalid UTF-16 BOM"),
+                expected: Box::new(crate::magic::Hex(BOM.to_le_bytes())),
+                found: Box::new(crate::magic::Hex(bom)),
             }),
         }
     }
@@ -1,7 +1,7 @@
 use core::fmt;
 use std::any::Any;
 use std::borrow::Cow;
-use crate::backtrace::{Backtrace, BacktraceFrame};
+pub use crate::backtrace::{Backtrace, BacktraceFrame};
 
 pub type BinResult<T> = Result<T, Error>;
 mod private {
@@ -132,8 +132,10 @@ impl dyn CustomError {
 }
 
 pub enum Error {
+    /// A magic number did not match. Both values are shown in hex.
     BadMagic {
         pos: u64,
+        expected: Box<dyn fmt::Debug + Send + Sync>,
         found: Box<dyn fmt::Debug + Send + Sync>,
     },
     AssertFail {
@@ -152,6 +154,14 @@ pub enum Error {
         pos: u64,
     },
 
+    /// A collection asked for more elements than the reader's
+    /// [`AllocationPolicy`](crate::io::AllocationPolicy) allows.
+    TooLarge {
+        pos: u64,
+        count: usize,
+        limit: usize,
+    },
+
     EnumErrors {
         pos: u64,
         variant_errors: Vec<(&'static str, Error)>,
@@ -159,6 +169,17 @@ pub enum Error {
 
     Backtrace(Backtrace),
 }
+impl Error {
+    /// Returns the source error, looking through any [`Error::Backtrace`].
+    #[must_use]
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            Self::Backtrace(backtrace) => &backtrace.error,
+            error => error,
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Self::Io(err)
@@ -167,11 +188,22 @@ impl From<std::io::Error> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::BadMagic { pos, found } => write!(f, "bad magic at 0x{pos:x}: {found:?}"),
+            Self::BadMagic {
+                pos,
+                expected,
+                found,
+            } => write!(
+                f,
+                "bad magic at 0x{pos:x}: expected {expected:?}, found {found:?}"
+            ),
             Self::AssertFail { pos, message } => write!(f, "{message} at 0x{pos:x}"),
             Self::Io(err) => fmt::Display::fmt(err, f),
             Self::Custom { pos, err } => write!(f, "{err} at 0x{pos:x}"),
             Self::NoVariantMatch { pos } => write!(f, "no variants matched at 0x{pos:x}"),
+            Self::TooLarge { pos, count, limit } => write!(
+                f,
+                "refusing to read {count} elements at 0x{pos:x}, the limit is {limit}"
+            ),
             Self::EnumErrors {
                 pos,
                 variant_errors,
@@ -0,0 +1,150 @@
+//! Text encodings for [`EncodedString`](crate::EncodedString).
+use crate::Endian;
+
+/// A character encoding that converts between bytes and `String`.
+pub trait TextEncoding {
+    /// The name used in error messages.
+    const NAME: &'static str;
+
+    /// The width in bytes of one code unit, and so of the terminator.
+    const UNIT: usize = 1;
+
+    /// Decodes `bytes`, returning the offset of the first byte that cannot be
+    /// decoded on failure.
+    fn decode(bytes: &[u8], endian: Endian) -> Result<String, usize>;
+
+    /// Encodes `text`, returning the output offset and the character that
+    /// cannot be encoded on failure.
+    fn encode(text: &str, endian: Endian) -> Result<Vec<u8>, (usize, char)>;
+}
+
+/// 7-bit ASCII.
+pub enum Ascii {}
+
+impl TextEncoding for Ascii {
+    const NAME: &'static str = "ASCII";
+
+    fn decode(bytes: &[u8], _endian: Endian) -> Result<String, usize> {
+        match bytes.iter().position(|b| !b.is_ascii()) {
+            Some(offset) => Err(offset),
+            None => Ok(bytes.iter().map(|&b| char::from(b)).collect()),
+        }
+    }
+
+    fn encode(text: &str, _endian: Endian) -> Result<Vec<u8>, (usize, char)> {
+        encode_bytes(text, |c| c.is_ascii().then_some(c as u8))
+    }
+}
+
+/// ISO 8859-1, which maps every byte to the code point of the same value.
+pub enum Latin1 {}
+
+impl TextEncoding for Latin1 {
+    const NAME: &'static str = "Latin-1";
+
+    fn decode(bytes: &[u8], _endian: Endian) -> Result<String, usize> {
+        Ok(bytes.iter().map(|&b| char::from(b)).collect())
+    }
+
+    fn encode(text: &str, _endian: Endian) -> Result<Vec<u8>, (usize, char)> {
+        encode_bytes(text, |c| u8::try_from(c).ok())
+    }
+}
+
+/// Windows-1252, Latin-1 with printable characters in place of most of the
+/// C1 control codes.
+pub enum Windows1252 {}
+
+/// The characters for bytes `0x80..=0x9f`; `None` marks undefined bytes.
+#[rustfmt::skip]
+const WINDOWS_1252_HIGH: [Option<char>; 32] = [
+    Some('\u{20ac}'), None, Some('\u{201a}'), Some('\u{0192}'),
+    Some('\u{201e}'), Some('\u{2026}'), Some('\u{2020}'), Some('\u{2021}'),
+    Some('\u{02c6}'), Some('\u{2030}'), Some('\u{0160}'), Some('\u{2039}'),
+    Some('\u{0152}'), None, Some('\u{017d}'), None,
+    None, Some('\u{2018}'), Some('\u{2019}'), Some('\u{201c}'),
+    Some('\u{201d}'), Some('\u{2022}'), Some('\u{2013}'), Some('\u{2014}'),
+    Some('\u{02dc}'), Some('\u{2122}'), Some('\u{0161}'), Some('\u{203a}'),
+    Some('\u{0153}'), None, Some('\u{017e}'), Some('\u{0178}'),
+];
+
+impl TextEncoding for Windows1252 {
+    const NAME: &'static str = "Windows-1252";
+
+    fn decode(bytes: &[u8], _endian: Endian) -> Result<String, usize> {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(offset, &b)| match b {
+                0x80..=0x9f => WINDOWS_1252_HIGH[usize::from(b - 0x80)].ok_or(offset),
+                _ => Ok(char::from(b)),
+            })
+            .collect()
+    }
+
+    fn encode(text: &str, _endian: Endian) -> Result<Vec<u8>, (usize, char)> {
+        encode_bytes(text, |c| match u8::try_from(c) {
+            Ok(b) if !(0x80..=0x9f).contains(&b) => Some(b),
+            _ => WINDOWS_1252_HIGH
+                .iter()
+                .position(|&high| high == Some(c))
+                .map(|index| 0x80 + index as u8),
+        })
+    }
+}
+
+/// UTF-16 with an optional byte order mark.
+///
+/// A leading BOM overrides the endianness when decoding, and encoding always
+/// writes one.
+pub enum Utf16Bom {}
+
+impl TextEncoding for Utf16Bom {
+    const NAME: &'static str = "UTF-16";
+    const UNIT: usize = 2;
+
+    fn decode(mut bytes: &[u8], mut endian: Endian) -> Result<String, usize> {
+        let mut start = 0;
+        if let [a, b, rest @ ..] = bytes
+            && let Ok(bom) = Endian::from_utf16_bom_bytes([*a, *b])
+        {
+            endian = bom;
+            bytes = rest;
+            start = 2;
+        }
+        if !bytes.len().is_multiple_of(2) {
+            return Err(start + bytes.len() - 1);
+        }
+        let units = bytes.chunks_exact(2).map(|unit| match endian {
+            Endian::Big => u16::from_be_bytes([unit[0], unit[1]]),
+            Endian::Little => u16::from_le_bytes([unit[0], unit[1]]),
+        });
+        let mut text = String::with_capacity(bytes.len() / 2);
+        let mut offset = start;
+        for c in char::decode_utf16(units) {
+            let c = c.map_err(|_| offset)?;
+            offset += c.len_utf16() * 2;
+            text.push(c);
+        }
+        Ok(text)
+    }
+
+    fn encode(text: &str, endian: Endian) -> Result<Vec<u8>, (usize, char)> {
+        let mut bytes = endian.into_utf16_bom_bytes().to_vec();
+        for unit in text.encode_utf16() {
+            bytes.extend(match endian {
+                Endian::Big => unit.to_be_bytes(),
+                Endian::Little => unit.to_le_bytes(),
+            });
+        }
+        Ok(bytes)
+    }
+}
+
+/// Encodes a single-byte encoding one character at a time.
+fn encode_bytes(text: &str, encode: impl Fn(char) -> Option<u8>) -> Result<Vec<u8>, (usize, char)> {
+    text.chars()
+        .enumerate()
+        .map(|(offset, c)| encode(c).ok_or((offset, c)))
+        .collect()
+}
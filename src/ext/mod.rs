@@ -1 +1,2 @@
-pub mod strings;
\ No newline at end of file
+pub mod encoding;
+pub mod strings;
@@ -1,10 +1,15 @@
 //! Type definitions for string readers.
 extern crate alloc;
+use crate::ext::encoding::{Ascii, Latin1, TextEncoding, Utf16Bom, Windows1252};
 use crate::io::read::Read;
 use crate::io::seek::Seek;
 use crate::io::write::Write;
-use crate::{BinRead, BinResult, BinWrite, Endian};
+use crate::read::borrowed::remaining;
+use crate::{BinRead, BinReadBorrowed, BinResult, BinWrite, Endian, Error};
 use core::fmt::{self};
+use core::marker::PhantomData;
+use core::str::Utf8Error;
+use std::io::Cursor;
 use std::string::{FromUtf8Error, FromUtf16Error};
 
 #[derive(Clone, Eq, PartialEq, Default)]
@@ -110,6 +115,83 @@ impl fmt::Display for NullString {
     }
 }
 
+/// A null-terminated byte string borrowed from the buffer it was read from;
+/// see [`BinReadBorrowed`].
+#[derive(Clone, Copy, Eq, PartialEq, Default)]
+pub struct NullStr<'de>(
+    /// The bytes before the terminator.
+    pub &'de [u8],
+);
+
+impl<'de> NullStr<'de> {
+    pub fn to_str(&self) -> Result<&'de str, Utf8Error> {
+        core::str::from_utf8(self.0)
+    }
+}
+
+impl<'de> BinReadBorrowed<'de> for NullStr<'de> {
+    type Args<'a> = ();
+
+    fn read_borrowed_options(
+        reader: &mut Cursor<&'de [u8]>,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send {
+        async move {
+            let pos = Cursor::position(reader);
+            let rest = remaining(reader);
+            let Some(len) = rest.iter().position(|&b| b == 0) else {
+                return Err(Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("no terminator after 0x{pos:x}"),
+                )));
+            };
+            Cursor::set_position(reader, pos + len as u64 + 1);
+            Ok(Self(&rest[..len]))
+        }
+    }
+}
+
+impl BinWrite for NullStr<'_> {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek + Send>(
+        &self,
+        writer: &mut W,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<()>> + Send
+    where
+        Self: Sync,
+    {
+        async {
+            writer.write_all(self.0).await?;
+            writer.write_all(&[0u8]).await?;
+            Ok(())
+        }
+    }
+}
+
+impl From<NullStr<'_>> for NullString {
+    fn from(s: NullStr<'_>) -> Self {
+        Self(s.0.to_vec())
+    }
+}
+
+impl fmt::Debug for NullStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NullStr(\"")?;
+        display_utf8(self.0, f, str::escape_debug)?;
+        write!(f, "\")")
+    }
+}
+
+impl fmt::Display for NullStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_utf8(self.0, f, str::chars)
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Default)]
 pub struct NullWideString(
     /// The raw wide byte string.
@@ -216,6 +298,523 @@ impl fmt::Debug for NullWideString {
     }
 }
 
+/// A byte string preceded by its length, stored as an `L`.
+#[derive(Clone, Eq, PartialEq, Default)]
+pub struct PrefixedString<L>(
+    /// The raw byte string.
+    pub Vec<u8>,
+    PhantomData<L>,
+);
+
+impl<L> BinRead for PrefixedString<L>
+where
+    L: for<'a> BinRead<Args<'a> = ()> + TryInto<usize> + Send,
+{
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek + Send>(
+        reader: &mut R,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let len = read_len::<L, _>(reader, endian).await?;
+            let value = <Vec<u8>>::read_options(reader, endian, len).await?;
+            Ok(Self(value, PhantomData))
+        }
+    }
+}
+
+impl<L> BinWrite for PrefixedString<L>
+where
+    L: for<'a> BinWrite<Args<'a> = ()> + TryFrom<usize> + Send + Sync,
+{
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek + Send>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<()>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            write_len::<L, _>(writer, endian, self.0.len()).await?;
+            writer.write_all(&self.0).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<L> From<&str> for PrefixedString<L> {
+    fn from(s: &str) -> Self {
+        Self(s.as_bytes().to_vec(), PhantomData)
+    }
+}
+
+impl<L> From<String> for PrefixedString<L> {
+    fn from(s: String) -> Self {
+        Self(s.into_bytes(), PhantomData)
+    }
+}
+
+impl<L> From<PrefixedString<L>> for Vec<u8> {
+    fn from(s: PrefixedString<L>) -> Self {
+        s.0
+    }
+}
+
+impl<L> TryFrom<PrefixedString<L>> for String {
+    type Error = FromUtf8Error;
+
+    fn try_from(value: PrefixedString<L>) -> Result<Self, Self::Error> {
+        String::from_utf8(value.0)
+    }
+}
+
+impl<L> core::ops::Deref for PrefixedString<L> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<L> core::ops::DerefMut for PrefixedString<L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<L> fmt::Debug for PrefixedString<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrefixedString(\"")?;
+        display_utf8(&self.0, f, str::escape_debug)?;
+        write!(f, "\")")
+    }
+}
+
+impl<L> fmt::Display for PrefixedString<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_utf8(&self.0, f, str::chars)
+    }
+}
+
+/// A UTF-16 string preceded by its length in code units, stored as an `L`.
+#[derive(Clone, Eq, PartialEq, Default)]
+pub struct PrefixedWideString<L>(
+    /// The raw wide byte string.
+    pub Vec<u16>,
+    PhantomData<L>,
+);
+
+impl<L> BinRead for PrefixedWideString<L>
+where
+    L: for<'a> BinRead<Args<'a> = ()> + TryInto<usize> + Send,
+{
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek + Send>(
+        reader: &mut R,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let len = read_len::<L, _>(reader, endian).await?;
+            let value = <Vec<u16>>::read_options(reader, endian, len).await?;
+            Ok(Self(value, PhantomData))
+        }
+    }
+}
+
+impl<L> BinWrite for PrefixedWideString<L>
+where
+    L: for<'a> BinWrite<Args<'a> = ()> + TryFrom<usize> + Send + Sync,
+{
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek + Send>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<()>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            write_len::<L, _>(writer, endian, self.0.len()).await?;
+            for unit in &self.0 {
+                unit.write_options(writer, endian, ()).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl<L> From<&str> for PrefixedWideString<L> {
+    fn from(s: &str) -> Self {
+        Self(s.encode_utf16().collect(), PhantomData)
+    }
+}
+
+impl<L> From<String> for PrefixedWideString<L> {
+    fn from(s: String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl<L> From<PrefixedWideString<L>> for Vec<u16> {
+    fn from(s: PrefixedWideString<L>) -> Self {
+        s.0
+    }
+}
+
+impl<L> TryFrom<PrefixedWideString<L>> for String {
+    type Error = FromUtf16Error;
+
+    fn try_from(value: PrefixedWideString<L>) -> Result<Self, Self::Error> {
+        String::from_utf16(&value.0)
+    }
+}
+
+impl<L> core::ops::Deref for PrefixedWideString<L> {
+    type Target = Vec<u16>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<L> core::ops::DerefMut for PrefixedWideString<L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<L> fmt::Display for PrefixedWideString<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_utf16(&self.0, f, core::iter::once)
+    }
+}
+
+impl<L> fmt::Debug for PrefixedWideString<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrefixedWideString(\"")?;
+        display_utf16(&self.0, f, char::escape_debug)?;
+        write!(f, "\")")
+    }
+}
+
+/// A byte string stored in exactly `N` bytes, padded at the end with `PAD`.
+///
+/// Trailing `PAD` bytes are stripped when reading and added back when
+/// writing. Writing a string longer than `N` is an error.
+#[derive(Clone, Eq, PartialEq, Default)]
+pub struct FixedString<const N: usize, const PAD: u8 = 0>(
+    /// The byte string, without padding.
+    pub Vec<u8>,
+);
+
+impl<const N: usize, const PAD: u8> BinRead for FixedString<N, PAD> {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek + Send>(
+        reader: &mut R,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let mut value = vec![0u8; N];
+            reader.read_exact(&mut value).await?;
+            let len = value.iter().rposition(|&b| b != PAD).map_or(0, |i| i + 1);
+            value.truncate(len);
+            Ok(Self(value))
+        }
+    }
+}
+
+impl<const N: usize, const PAD: u8> BinWrite for FixedString<N, PAD> {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek + Send>(
+        &self,
+        writer: &mut W,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<()>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            if self.0.len() > N {
+                return Err(Error::Custom {
+                    pos: writer.stream_position().await?,
+                    err: Box::new(format!(
+                        "string of {} bytes does not fit in a {N}-byte field",
+                        self.0.len()
+                    )),
+                });
+            }
+            writer.write_all(&self.0).await?;
+            writer.write_all(&[PAD; N][self.0.len()..]).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<const N: usize, const PAD: u8> From<&str> for FixedString<N, PAD> {
+    fn from(s: &str) -> Self {
+        Self(s.as_bytes().to_vec())
+    }
+}
+
+impl<const N: usize, const PAD: u8> From<String> for FixedString<N, PAD> {
+    fn from(s: String) -> Self {
+        Self(s.into_bytes())
+    }
+}
+
+impl<const N: usize, const PAD: u8> From<FixedString<N, PAD>> for Vec<u8> {
+    fn from(s: FixedString<N, PAD>) -> Self {
+        s.0
+    }
+}
+
+impl<const N: usize, const PAD: u8> TryFrom<FixedString<N, PAD>> for String {
+    type Error = FromUtf8Error;
+
+    fn try_from(value: FixedString<N, PAD>) -> Result<Self, Self::Error> {
+        String::from_utf8(value.0)
+    }
+}
+
+impl<const N: usize, const PAD: u8> core::ops::Deref for FixedString<N, PAD> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize, const PAD: u8> core::ops::DerefMut for FixedString<N, PAD> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const N: usize, const PAD: u8> fmt::Debug for FixedString<N, PAD> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FixedString(\"")?;
+        display_utf8(&self.0, f, str::escape_debug)?;
+        write!(f, "\")")
+    }
+}
+
+impl<const N: usize, const PAD: u8> fmt::Display for FixedString<N, PAD> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_utf8(&self.0, f, str::chars)
+    }
+}
+
+/// A string stored in the text encoding `E`.
+///
+/// With `None` as its arguments, the string ends at a zero code unit, which
+/// is consumed and written back. With `Some(len)`, it fills exactly `len`
+/// bytes; shorter strings are padded with zeroes when writing, and trailing
+/// zero code units are dropped when reading.
+#[derive(Clone, Eq, PartialEq, Default)]
+pub struct EncodedString<E>(
+    /// The decoded string.
+    pub String,
+    PhantomData<E>,
+);
+
+/// An ASCII string.
+pub type AsciiString = EncodedString<Ascii>;
+/// A Latin-1 string.
+pub type Latin1String = EncodedString<Latin1>;
+/// A Windows-1252 string.
+pub type Windows1252String = EncodedString<Windows1252>;
+/// A UTF-16 string with an optional byte order mark.
+pub type Utf16BomString = EncodedString<Utf16Bom>;
+
+impl<E: TextEncoding> BinRead for EncodedString<E> {
+    type Args<'a> = Option<usize>;
+
+    fn read_options<R: Read + Seek + Send>(
+        reader: &mut R,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let pos = reader.stream_position().await?;
+            let mut bytes = match args {
+                Some(len) => <Vec<u8>>::read_options(reader, endian, len).await?,
+                None => {
+                    let mut bytes = Vec::new();
+                    let mut unit = vec![0u8; E::UNIT];
+                    loop {
+                        reader.read_exact(&mut unit).await?;
+                        if unit.iter().all(|&b| b == 0) {
+                            break;
+                        }
+                        bytes.extend_from_slice(&unit);
+                    }
+                    bytes
+                }
+            };
+            while bytes.len() >= E::UNIT && bytes[bytes.len() - E::UNIT..].iter().all(|&b| b == 0) {
+                bytes.truncate(bytes.len() - E::UNIT);
+            }
+            match E::decode(&bytes, endian) {
+                Ok(text) => Ok(Self(text, PhantomData)),
+                Err(offset) => Err(Error::Custom {
+                    pos: pos + offset as u64,
+                    err: Box::new(format!(
+                        "byte 0x{:02x} is not valid {}",
+                        bytes[offset],
+                        E::NAME
+                    )),
+                }),
+            }
+        }
+    }
+}
+
+impl<E: TextEncoding> BinWrite for EncodedString<E> {
+    type Args<'a> = Option<usize>;
+
+    fn write_options<W: Write + Seek + Send>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<()>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let pos = writer.stream_position().await?;
+            let bytes = E::encode(&self.0, endian).map_err(|(offset, c)| Error::Custom {
+                pos: pos + offset as u64,
+                err: Box::new(format!("{c:?} cannot be encoded as {}", E::NAME)),
+            })?;
+            let padding = match args {
+                Some(len) => len.checked_sub(bytes.len()).ok_or_else(|| Error::Custom {
+                    pos,
+                    err: Box::new(format!(
+                        "string of {} bytes does not fit in a {len}-byte field",
+                        bytes.len()
+                    )),
+                })?,
+                None => E::UNIT,
+            };
+            writer.write_all(&bytes).await?;
+            writer.write_all(&vec![0u8; padding]).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<E> From<&str> for EncodedString<E> {
+    fn from(s: &str) -> Self {
+        Self(s.to_string(), PhantomData)
+    }
+}
+
+impl<E> From<String> for EncodedString<E> {
+    fn from(s: String) -> Self {
+        Self(s, PhantomData)
+    }
+}
+
+impl<E> From<EncodedString<E>> for String {
+    fn from(s: EncodedString<E>) -> Self {
+        s.0
+    }
+}
+
+impl<E> core::ops::Deref for EncodedString<E> {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> core::ops::DerefMut for EncodedString<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> fmt::Debug for EncodedString<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncodedString({:?})", self.0)
+    }
+}
+
+impl<E> fmt::Display for EncodedString<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Reads a length prefix of type `L`.
+fn read_len<L, R>(reader: &mut R, endian: Endian) -> impl Future<Output = BinResult<usize>> + Send
+where
+    L: for<'a> BinRead<Args<'a> = ()> + TryInto<usize> + Send,
+    R: Read + Seek + Send,
+{
+    async move {
+        let pos = reader.stream_position().await?;
+        let len = L::read_options(reader, endian, ()).await?;
+        len.try_into().map_err(|_| Error::Custom {
+            pos,
+            err: Box::new("string length does not fit in a usize".to_string()),
+        })
+    }
+}
+
+/// Writes `len` as a length prefix of type `L`, failing if it does not fit.
+fn write_len<L, W>(
+    writer: &mut W,
+    endian: Endian,
+    len: usize,
+) -> impl Future<Output = BinResult<()>> + Send
+where
+    L: for<'a> BinWrite<Args<'a> = ()> + TryFrom<usize> + Send + Sync,
+    W: Write + Seek + Send,
+{
+    async move {
+        let Ok(prefix) = L::try_from(len) else {
+            return Err(Error::Custom {
+                pos: writer.stream_position().await?,
+                err: Box::new(format!(
+                    "string of length {len} does not fit in a `{}` length prefix",
+                    core::any::type_name::<L>()
+                )),
+            });
+        };
+        prefix.write_options(writer, endian, ()).await
+    }
+}
+
 fn display_utf16<Transformer: Fn(char) -> O, O: Iterator<Item = char>>(
     input: &[u16],
     f: &mut fmt::Formatter<'_>,
@@ -256,3 +855,118 @@ fn display_utf8<'a, Transformer: Fn(&'a str) -> O, O: Iterator<Item = char> + 'a
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{AllocationPolicy, Limited};
+    use crate::{BinReaderExt, BinWriterExt};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn test_prefixed_string() -> Result<()> {
+        let mut data = Cursor::new(vec![0, 3, b'a', b'b', b'c', 2, 0x68, 0, 0x69, 0]);
+        let name = data.read_be::<PrefixedString<u16>>().await?;
+        assert_eq!(name.to_string(), "abc");
+        let wide = data.read_le::<PrefixedWideString<u8>>().await?;
+        assert_eq!(format!("{wide:?}"), "PrefixedWideString(\"hi\")");
+
+        let mut out = Cursor::new(Vec::new());
+        out.write_be(&name).await?;
+        out.write_le(&wide).await?;
+        assert_eq!(out.into_inner(), data.into_inner());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prefixed_string_overflow() -> Result<()> {
+        let mut out = Cursor::new(Vec::new());
+        let long = PrefixedString::<u8>::from("x".repeat(256));
+        let err = out.write_le(&long).await.unwrap_err();
+        assert!(matches!(err, Error::Custom { pos: 0, .. }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fixed_string() -> Result<()> {
+        let mut data = Cursor::new(b"__TEXT\0\0abc  ".to_vec());
+        let segment = data.read_le::<FixedString<8>>().await?;
+        assert_eq!(format!("{segment:?}"), "FixedString(\"__TEXT\")");
+        let label = data.read_le::<FixedString<5, b' '>>().await?;
+        assert_eq!(label.0, b"abc");
+
+        let mut out = Cursor::new(Vec::new());
+        out.write_le(&segment).await?;
+        out.write_le(&label).await?;
+        assert_eq!(out.get_ref(), data.get_ref());
+
+        let err = out
+            .write_le(&FixedString::<2>::from("abc"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Custom { pos: 13, .. }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_encoded_string() -> Result<()> {
+        let mut data = Cursor::new(vec![
+            b'c', 0xe9, 0x80, 0, 0xff, 0xfe, b'h', 0, b'i', 0, 0, 0,
+        ]);
+        let text = data.read_le::<Windows1252String>().await?;
+        assert_eq!(text.0, "c\u{e9}\u{20ac}");
+        let wide = data.read_be::<Utf16BomString>().await?;
+        assert_eq!(wide.0, "hi");
+
+        let mut out = Cursor::new(Vec::new());
+        out.write_le(&text).await?;
+        out.write_le(&wide).await?;
+        assert_eq!(out.get_ref(), data.get_ref());
+
+        let mut data = Cursor::new(b"ab\0\0".to_vec());
+        let fixed = data.read_le_args::<Latin1String>(Some(4)).await?;
+        assert_eq!(fixed.0, "ab");
+        let mut out = Cursor::new(Vec::new());
+        out.write_le_args(&fixed, Some(4)).await?;
+        assert_eq!(out.into_inner(), b"ab\0\0");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_encoded_string_huge_len() -> Result<()> {
+        let policy = AllocationPolicy {
+            max_count: Some(16),
+            ..Default::default()
+        };
+        let mut data = Limited::new(Cursor::new(b"abc".to_vec()), policy);
+        let err = data
+            .read_le_args::<Latin1String>(Some(usize::MAX))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::TooLarge { pos: 0, limit: 16, .. }));
+
+        let mut data = Cursor::new(b"abc".to_vec());
+        let err = data
+            .read_le_args::<Latin1String>(Some(usize::MAX))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_encoded_string_unmappable() -> Result<()> {
+        let mut data = Cursor::new(vec![b'o', b'k', 0x81, 0]);
+        let err = data.read_le::<Windows1252String>().await.unwrap_err();
+        assert!(matches!(err, Error::Custom { pos: 2, .. }));
+
+        let mut out = Cursor::new(Vec::new());
+        let err = out
+            .write_le(&AsciiString::from("caf\u{e9}"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Custom { pos: 3, .. }));
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+//! Types for reading values stored at an offset elsewhere in the stream.
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::{BacktraceFrame, BinRead, BinResult, ContextExt, Endian, NamedArgs};
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use std::io::SeekFrom;
+
+/// A [`FilePtr`] with an 8-bit offset.
+pub type FilePtr8<T> = FilePtr<u8, T>;
+/// A [`FilePtr`] with a 16-bit offset.
+pub type FilePtr16<T> = FilePtr<u16, T>;
+/// A [`FilePtr`] with a 32-bit offset.
+pub type FilePtr32<T> = FilePtr<u32, T>;
+/// A [`FilePtr`] with a 64-bit offset.
+pub type FilePtr64<T> = FilePtr<u64, T>;
+
+/// A value stored at an offset of type `P` from some base position.
+///
+/// Reading a `FilePtr` reads the offset, seeks to `offset` plus the base
+/// given in [`FilePtrArgs`], parses `T`, and then returns to the position just
+/// after the offset. Use [`FilePtr::read_deferred`] and
+/// [`FilePtr::after_parse`] to read the offset first and follow it later.
+pub struct FilePtr<P, T> {
+    /// The raw offset.
+    pub ptr: P,
+    /// The value, or `None` if the pointer has not been followed yet.
+    pub value: Option<T>,
+    pos: u64,
+}
+
+/// Arguments for reading a [`FilePtr`].
+#[derive(NamedArgs)]
+pub struct FilePtrArgs<Inner> {
+    /// The position the pointer is relative to.
+    #[named_args(default = 0)]
+    pub offset: u64,
+    /// The arguments passed through to the pointed-to value.
+    pub inner: Inner,
+}
+
+impl<Inner: Default> Default for FilePtrArgs<Inner> {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            inner: Inner::default(),
+        }
+    }
+}
+
+impl<P, T> FilePtr<P, T>
+where
+    P: for<'a> BinRead<Args<'a> = ()> + Into<u64> + Copy + Send,
+    T: BinRead + Send,
+{
+    /// Reads only the offset, leaving the value to be read by
+    /// [`after_parse`](Self::after_parse).
+    pub fn read_deferred<R: Read + Seek + Send>(
+        reader: &mut R,
+        endian: Endian,
+    ) -> impl Future<Output = BinResult<Self>> + Send {
+        async move {
+            let pos = reader.stream_position().await?;
+            let ptr = P::read_options(reader, endian, ()).await?;
+            Ok(Self {
+                ptr,
+                value: None,
+                pos,
+            })
+        }
+    }
+
+    /// Follows the offset and parses the value, returning to the current
+    /// position afterwards.
+    pub fn after_parse<R: Read + Seek + Send>(
+        &mut self,
+        reader: &mut R,
+        endian: Endian,
+        args: FilePtrArgs<T::Args<'_>>,
+    ) -> impl Future<Output = BinResult<()>> + Send {
+        async move {
+            let return_pos = reader.stream_position().await?;
+            let target = args.offset.wrapping_add(self.ptr.into());
+            let value = match reader.seek(SeekFrom::Start(target)).await {
+                Ok(_) => T::read_options(reader, endian, args.inner).await,
+                Err(err) => Err(err.into()),
+            };
+            reader.seek(SeekFrom::Start(return_pos)).await?;
+            let value = value.with_context(BacktraceFrame::Message(
+                format!(
+                    "While following pointer at 0x{:x} to 0x{target:x}",
+                    self.pos
+                )
+                .into(),
+            ))?;
+            self.value = Some(value);
+            Ok(())
+        }
+    }
+}
+
+impl<P, T> FilePtr<P, T> {
+    /// The position the offset itself was read from.
+    pub fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /// Consumes the pointer, returning the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pointer has not been followed yet.
+    pub fn into_inner(self) -> T {
+        self.value.expect("FilePtr has not been followed yet")
+    }
+}
+
+impl<P, T> BinRead for FilePtr<P, T>
+where
+    P: for<'a> BinRead<Args<'a> = ()> + Into<u64> + Copy + Send,
+    T: BinRead + Send,
+{
+    type Args<'a> = FilePtrArgs<T::Args<'a>>;
+
+    fn read_options<R: Read + Seek + Send>(
+        reader: &mut R,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let mut this = Self::read_deferred(reader, endian).await?;
+            if let Err(err) = this.after_parse(reader, endian, args).await {
+                return Err(crate::private::restore_position(reader, this.pos).await(err));
+            }
+            Ok(this)
+        }
+    }
+}
+
+impl<P, T> Deref for FilePtr<P, T> {
+    type Target = T;
+
+    /// # Panics
+    ///
+    /// Panics if the pointer has not been followed yet.
+    fn deref(&self) -> &Self::Target {
+        self.value.as_ref().expect("FilePtr has not been followed yet")
+    }
+}
+
+impl<P, T> DerefMut for FilePtr<P, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value.as_mut().expect("FilePtr has not been followed yet")
+    }
+}
+
+impl<P: fmt::Debug, T: fmt::Debug> fmt::Debug for FilePtr<P, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => value.fmt(f),
+            None => write!(f, "UnreadPointer({:?})", self.ptr),
+        }
+    }
+}
+
+impl<P: PartialEq, T: PartialEq> PartialEq for FilePtr<P, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr && self.value == other.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::NoSeek;
+    use crate::{BinReaderExt, Error};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[derive(BinRead, Debug)]
+    #[br(big)]
+    struct Table {
+        count: u8,
+        #[br(args { inner: count as usize })]
+        entries: FilePtr16<Vec<u8>>,
+        trailer: u8,
+    }
+
+    #[tokio::test]
+    async fn test_file_ptr() -> Result<()> {
+        let mut data = Cursor::new(vec![2, 0, 5, 0xff, 0, 7, 8]);
+        let table = data.read_le::<Table>().await?;
+        assert_eq!(*table.entries, [7, 8]);
+        assert_eq!((table.count, table.trailer), (2, 0xff));
+        assert_eq!(data.position(), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_ptr_base_offset() -> Result<()> {
+        let mut data = Cursor::new(vec![1, 0x10, 0x20]);
+        let args = FilePtr8::<u8>::args().offset(1).inner(()).finalize();
+        let ptr = FilePtr8::<u8>::read_args(&mut data, args).await?;
+        assert_eq!(ptr.into_inner(), 0x20);
+        assert_eq!(data.position(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_ptr_deferred() -> Result<()> {
+        let mut data = Cursor::new(vec![0, 0, 0, 5, 1, 0x2a, 0]);
+        let mut ptr = FilePtr32::<u16>::read_deferred(&mut data, Endian::Big).await?;
+        assert!(ptr.value.is_none());
+        let flag = data.read_be::<u8>().await?;
+        assert_eq!(flag, 1);
+        ptr.after_parse(&mut data, Endian::Little, FilePtrArgs::default())
+            .await?;
+        assert_eq!(*ptr, 0x2a);
+        assert_eq!(data.position(), 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_ptr_error() -> Result<()> {
+        let mut data = Cursor::new(vec![9, 0, 0]);
+        let err = data.read_be::<FilePtr8<u32>>().await.unwrap_err();
+        assert_eq!(data.position(), 0);
+        let Error::Backtrace(backtrace) = &err else {
+            panic!("expected a backtrace, got {err:?}");
+        };
+        assert!(matches!(*backtrace.error, Error::Io(_)));
+        assert!(matches!(
+            &backtrace.frames[0],
+            BacktraceFrame::Message(message) if message == "While following pointer at 0x0 to 0x9"
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_ptr_seek_error() -> Result<()> {
+        let mut data = NoSeek::new(Cursor::new(vec![9]));
+        let mut ptr = FilePtr8::<u8>::read_deferred(&mut data, Endian::Big).await?;
+        let err = ptr
+            .after_parse(&mut data, Endian::Big, FilePtrArgs::default())
+            .await
+            .unwrap_err();
+        let Error::Backtrace(backtrace) = &err else {
+            panic!("expected a backtrace, got {err:?}");
+        };
+        assert!(matches!(*backtrace.error, Error::Io(_)));
+        assert!(matches!(
+            &backtrace.frames[0],
+            BacktraceFrame::Message(message) if message == "While following pointer at 0x0 to 0x9"
+        ));
+        Ok(())
+    }
+}
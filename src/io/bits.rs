@@ -0,0 +1,608 @@
+//! Reading and writing values that do not start or end on a byte boundary.
+//!
+//! [`BitReader`] and [`BitWriter`] add `read_bits` and `write_bits` to a byte
+//! stream. Derived structs pack fields the same way with `#[br(bits = n)]`,
+//! `#[bw(bits = n)]` or `#[brw(bits = n)]`, in the order set by
+//! `#[brw(bit_order = ...)]` on the type.
+use crate::io::limit::AllocationPolicy;
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+use std::io::{Error, ErrorKind, SeekFrom};
+
+/// Which end of each byte bits are taken from first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The most significant bit first, as in network protocol headers.
+    #[default]
+    MsbFirst,
+    /// The least significant bit first, as in DEFLATE.
+    LsbFirst,
+}
+
+pub(crate) fn mask(n: u32) -> u64 {
+    if n >= 64 { u64::MAX } else { (1 << n) - 1 }
+}
+
+fn check_count(n: u32) -> std::io::Result<()> {
+    if n > 64 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("cannot move {n} bits at once, the limit is 64"),
+        ));
+    }
+    Ok(())
+}
+
+/// The bit-level state of a [`BitReader`]: the rest of a partly read byte.
+///
+/// Derived readers keep one of these for each run of `bits` fields.
+#[derive(Clone, Debug, Default)]
+pub struct BitUnpacker {
+    order: BitOrder,
+    byte: u8,
+    left: u32,
+}
+
+impl BitUnpacker {
+    pub fn new(order: BitOrder) -> Self {
+        Self {
+            order,
+            byte: 0,
+            left: 0,
+        }
+    }
+
+    /// Drops the rest of a partly read byte.
+    pub fn align(&mut self) {
+        self.left = 0;
+    }
+
+    /// Takes up to `n` bits from the current byte, returning them and how
+    /// many were taken.
+    fn take(&mut self, n: u32) -> (u64, u32) {
+        let count = n.min(self.left);
+        let shift = match self.order {
+            BitOrder::MsbFirst => self.left - count,
+            BitOrder::LsbFirst => 8 - self.left,
+        };
+        self.left -= count;
+        ((u64::from(self.byte) >> shift) & mask(count), count)
+    }
+}
+
+/// Reads `n` bits from `reader`, continuing from the partly read byte in
+/// `bits`.
+pub(crate) fn read_bits<R: Read + Send>(
+    reader: &mut R,
+    bits: &mut BitUnpacker,
+    n: u32,
+) -> impl Future<Output = std::io::Result<u64>> + Send {
+    async move {
+        check_count(n)?;
+        let mut value = 0;
+        let mut have = 0;
+        while have < n {
+            if bits.left == 0 {
+                bits.byte = reader.read_byte().await?;
+                bits.left = 8;
+            }
+            let (chunk, count) = bits.take(n - have);
+            value = match bits.order {
+                BitOrder::MsbFirst => (value << count) | chunk,
+                BitOrder::LsbFirst => value | (chunk << have),
+            };
+            have += count;
+        }
+        Ok(value)
+    }
+}
+
+/// The bit-level state of a [`BitWriter`]: a partly written byte.
+///
+/// Derived writers keep one of these for each run of `bits` fields.
+#[derive(Clone, Debug, Default)]
+pub struct BitPacker {
+    order: BitOrder,
+    byte: u8,
+    used: u32,
+}
+
+impl BitPacker {
+    pub fn new(order: BitOrder) -> Self {
+        Self {
+            order,
+            byte: 0,
+            used: 0,
+        }
+    }
+
+    /// Adds as many of the low `n` bits of `value` as fit in the current
+    /// byte, after the `done` already added, and returns how many fit.
+    fn put(&mut self, value: u64, n: u32, done: u32) -> u32 {
+        let count = (n - done).min(8 - self.used);
+        let chunk = match self.order {
+            BitOrder::MsbFirst => value >> (n - done - count),
+            BitOrder::LsbFirst => value >> done,
+        } & mask(count);
+        let shift = match self.order {
+            BitOrder::MsbFirst => 8 - self.used - count,
+            BitOrder::LsbFirst => self.used,
+        };
+        // Lint: `chunk` has at most `8 - shift` bits
+        #[allow(clippy::cast_possible_truncation)]
+        let chunk = (chunk << shift) as u8;
+        self.byte |= chunk;
+        self.used += count;
+        count
+    }
+
+    /// Takes the current byte, padded with zero bits, if it has any bits, or
+    /// only if it is full.
+    fn take(&mut self, full: bool) -> Option<u8> {
+        if self.used == 0 || (full && self.used < 8) {
+            return None;
+        }
+        let byte = self.byte;
+        self.byte = 0;
+        self.used = 0;
+        Some(byte)
+    }
+}
+
+/// Writes the low `n` bits of `value` to `writer`, continuing the partly
+/// written byte in `bits`. Fails without writing if `value` does not fit.
+pub(crate) fn write_bits<W: Write + Send>(
+    writer: &mut W,
+    bits: &mut BitPacker,
+    value: u64,
+    n: u32,
+) -> impl Future<Output = std::io::Result<()>> + Send {
+    async move {
+        check_count(n)?;
+        BitOverflow::check(value, n).map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+        let mut done = 0;
+        while done < n {
+            done += bits.put(value, n, done);
+            if let Some(byte) = bits.take(true) {
+                writer.write_all(&[byte]).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes out the partly written byte in `bits`, padded with zero bits.
+pub(crate) fn align_bits<W: Write + Send>(
+    writer: &mut W,
+    bits: &mut BitPacker,
+) -> impl Future<Output = std::io::Result<()>> + Send {
+    async move {
+        if let Some(byte) = bits.take(false) {
+            writer.write_all(&[byte]).await?;
+        }
+        Ok(())
+    }
+}
+
+/// A value that does not fit in the bits set aside for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitOverflow {
+    pub value: u64,
+    pub width: u32,
+}
+
+impl BitOverflow {
+    pub(crate) fn check(value: u64, width: u32) -> Result<(), Self> {
+        if value & !mask(width) == 0 {
+            Ok(())
+        } else {
+            Err(Self { value, width })
+        }
+    }
+}
+
+impl core::fmt::Display for BitOverflow {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} does not fit in {} bits", self.value, self.width)
+    }
+}
+
+impl std::error::Error for BitOverflow {}
+
+/// A type that a bit-packed field can hold.
+pub trait BitValue: Sized {
+    /// The widest field the type can hold.
+    const BITS: u32;
+
+    /// Converts the raw bits, or returns `None` if they do not fit.
+    fn from_bits(bits: u64) -> Option<Self>;
+
+    fn to_bits(&self) -> u64;
+}
+
+macro_rules! bit_value_impl {
+    ($($ty:ty),*) => {
+        $(
+            impl BitValue for $ty {
+                const BITS: u32 = <$ty>::BITS;
+
+                fn from_bits(bits: u64) -> Option<Self> {
+                    Self::try_from(bits).ok()
+                }
+
+                fn to_bits(&self) -> u64 {
+                    u64::from(*self)
+                }
+            }
+        )*
+    };
+}
+
+bit_value_impl!(u8, u16, u32, u64);
+
+impl BitValue for bool {
+    const BITS: u32 = 1;
+
+    fn from_bits(bits: u64) -> Option<Self> {
+        match bits {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn to_bits(&self) -> u64 {
+        u64::from(*self)
+    }
+}
+
+/// A reader that can also read a number of bits at a time.
+///
+/// Anything else read through it, including any [`BinRead`](crate::BinRead)
+/// type, starts at the next whole byte; so does any seek, including asking
+/// for the position.
+pub struct BitReader<R> {
+    inner: R,
+    bits: BitUnpacker,
+}
+
+impl<R> BitReader<R> {
+    pub fn new(inner: R, order: BitOrder) -> Self {
+        Self {
+            inner,
+            bits: BitUnpacker::new(order),
+        }
+    }
+
+    /// Drops the rest of a partly read byte.
+    pub fn align(&mut self) {
+        self.bits.align();
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Send> BitReader<R> {
+    /// Reads `n` bits, up to 64, as an unsigned integer.
+    pub fn read_bits(&mut self, n: u32) -> impl Future<Output = std::io::Result<u64>> + Send {
+        read_bits(&mut self.inner, &mut self.bits, n)
+    }
+
+    pub fn read_bit(&mut self) -> impl Future<Output = std::io::Result<bool>> + Send {
+        async move { Ok(self.read_bits(1).await? == 1) }
+    }
+}
+
+impl<R: Read + Send> Read for BitReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        self.bits.align();
+        self.inner.read(buf)
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.inner.flush()
+    }
+
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        self.inner.allocation_policy()
+    }
+}
+
+impl<R: Seek + Send> Seek for BitReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        self.bits.align();
+        self.inner.seek(pos)
+    }
+}
+
+/// A writer that can also write a number of bits at a time.
+///
+/// Anything else written through it starts at the next whole byte, with the
+/// rest of a partly written byte filled with zero bits; the same happens on
+/// any seek, including asking for the position. Call
+/// [`align`](Self::align) or [`into_inner`](Self::into_inner) to write out a
+/// trailing partial byte.
+pub struct BitWriter<W> {
+    inner: W,
+    bits: BitPacker,
+}
+
+impl<W> BitWriter<W> {
+    pub fn new(inner: W, order: BitOrder) -> Self {
+        Self {
+            inner,
+            bits: BitPacker::new(order),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write + Send> BitWriter<W> {
+    /// Writes the low `n` bits of `value`, up to 64, failing if `value` does
+    /// not fit.
+    pub fn write_bits(
+        &mut self,
+        value: u64,
+        n: u32,
+    ) -> impl Future<Output = std::io::Result<()>> + Send {
+        write_bits(&mut self.inner, &mut self.bits, value, n)
+    }
+
+    pub fn write_bit(&mut self, bit: bool) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.write_bits(u64::from(bit), 1)
+    }
+
+    /// Writes out a partly written byte, padded with zero bits.
+    pub fn align(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        align_bits(&mut self.inner, &mut self.bits)
+    }
+
+    /// Writes out a partly written byte and returns the inner writer.
+    pub fn into_inner(mut self) -> impl Future<Output = std::io::Result<W>> + Send {
+        async move {
+            self.align().await?;
+            Ok(self.inner)
+        }
+    }
+}
+
+impl<W: Write + Send> Write for BitWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move {
+            self.align().await?;
+            self.inner.write(buf).await
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            self.align().await?;
+            self.inner.flush().await
+        }
+    }
+}
+
+impl<W: Write + Seek + Send> Seek for BitWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            self.align().await?;
+            self.inner.seek(pos).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn test_bit_orders() -> Result<()> {
+        let data = vec![0b1011_0010, 0b0111_1100, 0xab];
+        let mut msb = BitReader::new(Cursor::new(data.clone()), BitOrder::MsbFirst);
+        assert_eq!(msb.read_bits(3).await?, 0b101);
+        assert_eq!(msb.read_bits(5).await?, 0b10010);
+        assert_eq!(msb.read_bits(13).await?, 0b0_1111_1001_0101);
+        assert!(!msb.read_bit().await?);
+
+        let mut lsb = BitReader::new(Cursor::new(data), BitOrder::LsbFirst);
+        assert_eq!(lsb.read_bits(3).await?, 0b010);
+        assert_eq!(lsb.read_bits(5).await?, 0b10110);
+        assert_eq!(lsb.read_bits(13).await?, 0b0_1011_0111_1100);
+        assert!(lsb.read_bit().await?);
+        assert!(lsb.read_bits(65).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() -> Result<()> {
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            let fields = [(5, 3), (17, 5), (0x1abc, 13), (u64::MAX, 64), (1, 1)];
+            let mut writer = BitWriter::new(Cursor::new(Vec::new()), order);
+            for (value, n) in fields {
+                writer.write_bits(value, n).await?;
+            }
+            let err = writer.write_bits(8, 3).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            let data = writer.into_inner().await?.into_inner();
+            assert_eq!(data.len(), 11);
+
+            let mut reader = BitReader::new(Cursor::new(data), order);
+            for (value, n) in fields {
+                assert_eq!(reader.read_bits(n).await?, value);
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bytes_align() -> Result<()> {
+        let mut reader = BitReader::new(Cursor::new(vec![0xf0, 0x12, 0x34]), BitOrder::MsbFirst);
+        assert_eq!(reader.read_bits(4).await?, 0xf);
+        assert_eq!(reader.read_be::<u16>().await?, 0x1234);
+
+        let mut writer = BitWriter::new(Cursor::new(Vec::new()), BitOrder::LsbFirst);
+        writer.write_bits(0b101, 3).await?;
+        writer.write_be(&0x1234u16).await?;
+        writer.write_bit(true).await?;
+        assert_eq!(
+            writer.into_inner().await?.into_inner(),
+            [0b101, 0x12, 0x34, 1]
+        );
+        Ok(())
+    }
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(big)]
+    struct Header {
+        #[brw(bits = 4)]
+        version: u8,
+        #[brw(bits = 4)]
+        ihl: u8,
+        #[brw(bits = 6)]
+        dscp: u8,
+        #[brw(bits = 1)]
+        congested: bool,
+        #[brw(bits = 13)]
+        offset: u16,
+        ttl: u8,
+        #[brw(bits(3))]
+        flags: u8,
+    }
+
+    #[tokio::test]
+    async fn test_derive_bits() -> Result<()> {
+        let header = Header {
+            version: 4,
+            ihl: 5,
+            dscp: 0b10_1110,
+            congested: true,
+            offset: 0x1234,
+            ttl: 64,
+            flags: 0b011,
+        };
+        let mut out = Cursor::new(Vec::new());
+        out.write_be(&header).await?;
+        // `offset` starts in the last bit of the `dscp` byte, and each run of
+        // bit fields is padded out to a whole byte.
+        assert_eq!(
+            out.get_ref(),
+            &[0x45, 0b1011_1011, 0b0010_0011, 0b0100_0000, 64, 0b0110_0000]
+        );
+        out.set_position(0);
+        assert_eq!(out.read_be::<Header>().await?, header);
+
+        let bad = Header { ihl: 16, ..header };
+        let err = Cursor::new(Vec::new()).write_be(&bad).await.unwrap_err();
+        assert!(format!("{err:?}").contains("'ihl'"));
+        Ok(())
+    }
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(bit_order = BitOrder::LsbFirst)]
+    struct Flags(
+        #[brw(bits = 1)] bool,
+        #[brw(bits = 2)] u8,
+        #[brw(bits = 9)] u16,
+    );
+
+    #[tokio::test]
+    async fn test_derive_lsb_first() -> Result<()> {
+        let mut out = Cursor::new(Vec::new());
+        out.write_le(&Flags(true, 2, 0x1ff)).await?;
+        assert_eq!(out.get_ref(), &[0b1111_1101, 0b0000_1111]);
+        out.set_position(0);
+        assert_eq!(out.read_le::<Flags>().await?, Flags(true, 2, 0x1ff));
+        Ok(())
+    }
+
+    #[crate::bitfield(u32)]
+    struct Ipv4Word {
+        /// The IP version, 4.
+        #[bits(4)]
+        version: u8,
+        #[bits(4)]
+        ihl: u8,
+        #[bits(6)]
+        dscp: u8,
+        #[bits(2)]
+        ecn: u8,
+        #[bits(16)]
+        total_length: u16,
+    }
+
+    #[tokio::test]
+    async fn test_bitfield() -> Result<()> {
+        let mut word = Ipv4Word::default();
+        word.set_version(4)?;
+        word.set_ihl(5)?;
+        word.set_ecn(1)?;
+        word.set_total_length(84)?;
+        assert_eq!(word.into_bits(), 0x4501_0054);
+        assert_eq!(
+            word.set_ihl(16),
+            Err(BitOverflow {
+                value: 16,
+                width: 4
+            })
+        );
+        assert_eq!(word.ihl(), 5);
+        assert_eq!(
+            format!("{word:?}"),
+            "Ipv4Word { version: 4, ihl: 5, dscp: 0, ecn: 1, total_length: 84 }"
+        );
+
+        let mut out = Cursor::new(Vec::new());
+        out.write_be(&word).await?;
+        out.write_le(&word).await?;
+        assert_eq!(
+            out.get_ref(),
+            &[0x45, 0x01, 0x00, 0x54, 0x54, 0x00, 0x01, 0x45]
+        );
+        out.set_position(0);
+        assert_eq!(out.read_be::<Ipv4Word>().await?, word);
+        assert_eq!(out.read_le::<Ipv4Word>().await?, word);
+        Ok(())
+    }
+
+    #[crate::bitfield(u8, bit_order = BitOrder::LsbFirst)]
+    struct Mode {
+        #[bits(1)]
+        read: bool,
+        #[bits(1)]
+        write: bool,
+        #[bits(3)]
+        kind: u8,
+    }
+
+    #[test]
+    fn test_bitfield_lsb_first() {
+        let mut mode = Mode::from(0b1110_1101);
+        assert!(mode.read());
+        assert!(!mode.write());
+        assert_eq!(mode.kind(), 0b011);
+        mode.set_write(true).unwrap();
+        mode.set_kind(0b100).unwrap();
+        assert_eq!(u8::from(mode), 0b1111_0011);
+    }
+}
@@ -0,0 +1,484 @@
+//! Buffering for streams where every call is expensive, such as files.
+//!
+//! Each primitive read asks for the stream position and then reads a handful
+//! of bytes, so parsing straight from a `File` costs two syscalls per field.
+//! [`BufReader`] and [`BufWriter`] batch the underlying calls and answer
+//! position queries from what they already know.
+use crate::io::limit::AllocationPolicy;
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+use std::io::SeekFrom;
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Adds buffering to a reader.
+///
+/// Seeks that land inside the buffered bytes only move the cursor, so the
+/// back-and-forth of error recovery and look-ahead stays in memory.
+pub struct BufReader<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+    /// The position of `inner`, which is the end of the buffered bytes, once
+    /// it has been asked for.
+    inner_pos: Option<u64>,
+}
+
+impl<R> BufReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+            inner_pos: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// The bytes read from `inner` but not yet consumed.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.filled]
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// The inner reader. Reading from it or seeking it directly leaves the
+    /// buffer out of step, so call [`BufReader::discard_buffer`] afterwards.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the inner reader. Any buffered bytes are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Drops the buffered bytes and forgets the cached position.
+    pub fn discard_buffer(&mut self) {
+        self.pos = 0;
+        self.filled = 0;
+        self.inner_pos = None;
+    }
+
+    fn consume(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.filled - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        n
+    }
+}
+
+impl<R: Read + Send> BufReader<R> {
+    fn fill_buf(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            let n = self.inner.read(&mut self.buf).await?;
+            // At the end of the stream the old bytes stay around so a seek
+            // back into them is still free.
+            if n > 0 {
+                self.pos = 0;
+                self.filled = n;
+                if let Some(inner_pos) = &mut self.inner_pos {
+                    *inner_pos += n as u64;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+impl<R: Read + Send> Read for BufReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move {
+            if self.pos == self.filled {
+                // Nothing is gained by copying a large read through the buffer.
+                if buf.len() >= self.buf.len() {
+                    // The buffer no longer ends where `inner` is, so it can't
+                    // be used to answer seeks.
+                    self.pos = 0;
+                    self.filled = 0;
+                    let n = self.inner.read(buf).await?;
+                    if let Some(inner_pos) = &mut self.inner_pos {
+                        *inner_pos += n as u64;
+                    }
+                    return Ok(n);
+                }
+                self.fill_buf().await?;
+            }
+            Ok(self.consume(buf))
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.inner.flush()
+    }
+
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        self.inner.allocation_policy()
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            let mut n = self.consume(buf);
+            while n < buf.len() {
+                let count = self.read(&mut buf[n..]).await?;
+                if count == 0 {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "failed to fill whole buffer",
+                    ));
+                }
+                n += count;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl<R: Seek + Send> BufReader<R> {
+    fn inner_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            match self.inner_pos {
+                Some(pos) => Ok(pos),
+                None => {
+                    let pos = self.inner.stream_position().await?;
+                    self.inner_pos = Some(pos);
+                    Ok(pos)
+                }
+            }
+        }
+    }
+}
+
+impl<R: Seek + Send> Seek for BufReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            if !matches!(pos, SeekFrom::End(_)) {
+                let inner_pos = self.inner_position().await?;
+                let start = inner_pos - self.filled as u64;
+                let target = match pos {
+                    SeekFrom::Current(offset) => {
+                        (start + self.pos as u64).checked_add_signed(offset)
+                    }
+                    SeekFrom::Start(target) => Some(target),
+                    SeekFrom::End(_) => None,
+                };
+                if let Some(target) = target
+                    && (start..=inner_pos).contains(&target)
+                {
+                    self.pos = (target - start) as usize;
+                    return Ok(target);
+                }
+            }
+
+            // Relative seeks are made from the logical position, which the
+            // inner stream is ahead of by the unread bytes.
+            let pos = match pos {
+                SeekFrom::Current(offset) => {
+                    SeekFrom::Current(offset.saturating_sub((self.filled - self.pos) as i64))
+                }
+                pos => pos,
+            };
+            self.discard_buffer();
+            let new_pos = self.inner.seek(pos).await?;
+            self.inner_pos = Some(new_pos);
+            Ok(new_pos)
+        }
+    }
+
+    fn stream_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let inner_pos = self.inner_position().await?;
+            Ok(inner_pos - (self.filled - self.pos) as u64)
+        }
+    }
+}
+
+/// Adds buffering to a writer.
+///
+/// Writes are collected until the buffer is full, the writer is flushed or
+/// seeked, or [`BufWriter::into_inner`] is called. Nothing is written when
+/// a `BufWriter` is dropped, so finish with one of those.
+pub struct BufWriter<W> {
+    inner: W,
+    buf: Vec<u8>,
+    /// The position of `inner`, which is the start of the buffered bytes,
+    /// once it has been asked for.
+    inner_pos: Option<u64>,
+}
+
+impl<W> BufWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::with_capacity(capacity),
+            inner_pos: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// The bytes written but not yet passed on to `inner`.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write + Send> BufWriter<W> {
+    fn flush_buf(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            if !self.buf.is_empty() {
+                self.inner.write_all(&self.buf).await?;
+                if let Some(inner_pos) = &mut self.inner_pos {
+                    *inner_pos += self.buf.len() as u64;
+                }
+                self.buf.clear();
+            }
+            Ok(())
+        }
+    }
+
+    /// Writes out the buffer and returns the inner writer.
+    pub fn into_inner(mut self) -> impl Future<Output = std::io::Result<W>> + Send {
+        async move {
+            self.flush_buf().await?;
+            Ok(self.inner)
+        }
+    }
+}
+
+impl<W: Write + Send> Write for BufWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move {
+            if self.buf.len() + buf.len() > self.buf.capacity() {
+                self.flush_buf().await?;
+            }
+            if buf.len() >= self.buf.capacity() {
+                let n = self.inner.write(buf).await?;
+                if let Some(inner_pos) = &mut self.inner_pos {
+                    *inner_pos += n as u64;
+                }
+                Ok(n)
+            } else {
+                self.buf.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            self.flush_buf().await?;
+            self.inner.flush().await
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            let mut n = 0;
+            while n < buf.len() {
+                n += self.write(&buf[n..]).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl<W: Write + Seek + Send> Seek for BufWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            self.flush_buf().await?;
+            let new_pos = self.inner.seek(pos).await?;
+            self.inner_pos = Some(new_pos);
+            Ok(new_pos)
+        }
+    }
+
+    fn stream_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let inner_pos = match self.inner_pos {
+                Some(pos) => pos,
+                None => {
+                    let pos = self.inner.stream_position().await?;
+                    self.inner_pos = Some(pos);
+                    pos
+                }
+            };
+            Ok(inner_pos + self.buf.len() as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    /// Counts the calls that reach the wrapped stream.
+    #[derive(Default)]
+    struct Counting {
+        inner: Cursor<Vec<u8>>,
+        reads: usize,
+        writes: usize,
+        seeks: usize,
+    }
+
+    impl Read for Counting {
+        fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+            self.reads += 1;
+            self.inner.read(buf)
+        }
+
+        fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+            async { Ok(()) }
+        }
+    }
+
+    impl Write for Counting {
+        fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+            self.writes += 1;
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+            async { Ok(()) }
+        }
+    }
+
+    impl Seek for Counting {
+        fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+            self.seeks += 1;
+            self.inner.seek(pos)
+        }
+    }
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(big)]
+    struct Header {
+        kind: u8,
+        flags: u16,
+        size: u32,
+        offset: u64,
+    }
+
+    #[tokio::test]
+    async fn test_reader_batches_calls() -> Result<()> {
+        let data = (0u8..60).collect::<Vec<_>>();
+        let mut reader = BufReader::with_capacity(
+            64,
+            Counting {
+                inner: Cursor::new(data),
+                ..Default::default()
+            },
+        );
+        let headers = reader.read_be_args::<Vec<Header>>(4).await?;
+        assert_eq!(headers[1].kind, 15);
+        assert_eq!(headers[3].offset, 0x3435_3637_3839_3a3b);
+        assert_eq!(reader.get_ref().reads, 1);
+        assert_eq!(reader.get_ref().seeks, 1);
+
+        reader.seek(SeekFrom::Current(-45)).await?;
+        assert_eq!(reader.read_be::<u16>().await?, 0x0f10);
+        reader.seek(SeekFrom::Start(58)).await?;
+        assert_eq!(reader.stream_position().await?, 58);
+        assert!(reader.read_be::<u32>().await.is_err());
+        assert_eq!(reader.stream_position().await?, 58);
+        assert_eq!(reader.get_ref().seeks, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_seeks_outside_buffer() -> Result<()> {
+        let data = (0u8..32).collect::<Vec<_>>();
+        let mut reader = BufReader::with_capacity(8, Cursor::new(data));
+        assert_eq!(reader.read_be::<u16>().await?, 0x0001);
+        reader.seek(SeekFrom::Current(10)).await?;
+        assert_eq!(reader.read_be::<u8>().await?, 12);
+        reader.seek(SeekFrom::End(-1)).await?;
+        assert_eq!(reader.read_be::<u8>().await?, 31);
+        reader.seek(SeekFrom::Start(3)).await?;
+        let mut buf = [0; 16];
+        reader.read_exact(&mut buf).await?;
+        assert_eq!(buf[15], 18);
+        assert_eq!(reader.stream_position().await?, 19);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_seek_after_large_read() -> Result<()> {
+        let data = (0u8..32).collect::<Vec<_>>();
+        let mut reader = BufReader::with_capacity(8, Cursor::new(data));
+        let mut buf = [0; 16];
+        reader.read_exact(&mut buf[..2]).await?;
+        reader.read_exact(&mut buf[..6]).await?;
+        // Bypasses the buffer, which still holds bytes 0..8.
+        reader.read_exact(&mut buf).await?;
+        assert_eq!(buf[0], 8);
+        reader.seek(SeekFrom::Start(17)).await?;
+        assert_eq!(reader.read_be::<u8>().await?, 17);
+        reader.seek(SeekFrom::Start(1)).await?;
+        assert_eq!(reader.read_be::<u8>().await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_writer_flushes_on_seek() -> Result<()> {
+        let mut writer = BufWriter::with_capacity(32, Counting::default());
+        let header = Header {
+            kind: 1,
+            flags: 2,
+            size: 3,
+            offset: 4,
+        };
+        writer.write_be(&header).await?;
+        writer.write_be(&header).await?;
+        assert_eq!(writer.stream_position().await?, 30);
+        assert_eq!(writer.get_ref().writes, 0);
+
+        writer.seek(SeekFrom::Start(1)).await?;
+        assert_eq!(writer.get_ref().writes, 1);
+        writer.write_be(&0xffffu16).await?;
+        let inner = writer.into_inner().await?;
+        assert_eq!(inner.writes, 2);
+
+        let mut data = inner.inner;
+        data.set_position(15);
+        assert_eq!(data.read_be::<Header>().await?, header);
+        data.set_position(0);
+        assert_eq!(data.read_be::<u32>().await?, 0x01ff_ff00);
+        Ok(())
+    }
+}
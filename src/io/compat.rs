@@ -0,0 +1,221 @@
+//! Shared machinery for exposing this crate's streams through poll-based
+//! runtime traits.
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+/// The result of one finished operation.
+pub(crate) enum Done {
+    Read(std::io::Result<Vec<u8>>),
+    Write(std::io::Result<usize>),
+    Flush(std::io::Result<()>),
+    Seek(std::io::Result<u64>),
+}
+
+type Op<T> = Pin<Box<dyn Future<Output = (T, Done)> + Send>>;
+
+/// The call that started an operation. A poll with an equal tag continues
+/// the operation; any other poll finishes it and starts its own.
+#[derive(Clone, PartialEq)]
+enum Tag {
+    /// Any read, since the bytes it took are kept whatever buffer it was for.
+    Read,
+    Write(Vec<u8>),
+    Flush,
+    Seek(SeekFrom),
+}
+
+enum State<T> {
+    Idle(T),
+    Busy(Op<T>, Tag),
+    Poisoned,
+}
+
+/// Runs one async operation on a stream at a time, taking ownership of the
+/// stream while the operation is in flight so it can be polled without
+/// borrowing.
+///
+/// An operation that is still in flight when a different call is made, such
+/// as a read cancelled by `select!`, is driven to completion first. The
+/// bytes a read took from the stream are kept for the next read, and seeks
+/// and writes take them into account. The results of other cancelled
+/// operations are dropped, so a result only reaches the call that started
+/// it, or a repeat of that call with the same arguments.
+pub(crate) struct Driver<T> {
+    state: State<T>,
+    /// Bytes read from the stream but not yet handed to a caller.
+    unread: Vec<u8>,
+}
+
+// The stream is only ever moved in and out by value, never pinned in place.
+impl<T> Unpin for Driver<T> {}
+
+impl<T: Send + 'static> Driver<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Self {
+            state: State::Idle(inner),
+            unread: Vec::new(),
+        }
+    }
+
+    pub(crate) fn get_ref(&self) -> Option<&T> {
+        match &self.state {
+            State::Idle(inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> Option<T> {
+        match self.state {
+            State::Idle(inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// Polls the operation `tag` started, or starts it with `start` once any
+    /// other operation in flight has finished. `start` also gets the unread
+    /// bytes.
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+        tag: Tag,
+        start: impl FnOnce(T, &mut Vec<u8>) -> Op<T>,
+    ) -> Poll<Done> {
+        let mut start = Some(start);
+        loop {
+            match core::mem::replace(&mut self.state, State::Poisoned) {
+                State::Idle(inner) => {
+                    let start = start.take().expect("operation started twice");
+                    self.state = State::Busy(start(inner, &mut self.unread), tag.clone());
+                }
+                State::Busy(mut op, op_tag) => match op.as_mut().poll(cx) {
+                    Poll::Pending => {
+                        self.state = State::Busy(op, op_tag);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready((inner, done)) => {
+                        self.state = State::Idle(inner);
+                        if op_tag == tag {
+                            return Poll::Ready(done);
+                        }
+                        // A read that was cancelled before it finished.
+                        if let Done::Read(Ok(data)) = done {
+                            self.unread.extend_from_slice(&data);
+                        }
+                    }
+                },
+                State::Poisoned => panic!("stream operation panicked"),
+            }
+        }
+    }
+}
+
+impl<T: Read + Send + 'static> Driver<T> {
+    /// Reads into `buf`, returning how many bytes were filled.
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let len = buf.len();
+        let done = ready!(self.poll(cx, Tag::Read, |mut inner, unread| {
+            let unread = core::mem::take(unread);
+            Box::pin(async move {
+                if !unread.is_empty() {
+                    return (inner, Done::Read(Ok(unread)));
+                }
+                let mut data = vec![0; len];
+                let result = inner.read(&mut data).await.map(|n| {
+                    data.truncate(n);
+                    data
+                });
+                (inner, Done::Read(result))
+            })
+        }));
+        let Done::Read(data) = done else {
+            unreachable!("a read finished as another operation")
+        };
+        let data = data?;
+        // A read retried with a smaller buffer, or served from the unread
+        // bytes, can have more than fits; the rest waits for the next read.
+        let n = data.len().min(len);
+        buf[..n].copy_from_slice(&data[..n]);
+        self.unread.splice(0..0, data[n..].iter().copied());
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<T: Write + Send + 'static> Driver<T> {
+    pub(crate) fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let done = ready!(
+            self.poll(cx, Tag::Write(buf.to_vec()), |mut inner, unread| {
+                let data = buf.to_vec();
+                let read_ahead = !unread.is_empty();
+                Box::pin(async move {
+                    // The stream is past the unread bytes, so the write would
+                    // land in the wrong place.
+                    if read_ahead {
+                        let err = std::io::Error::other(
+                            "cannot write with bytes from a cancelled read still unread",
+                        );
+                        return (inner, Done::Write(Err(err)));
+                    }
+                    let result = inner.write(&data).await;
+                    (inner, Done::Write(result))
+                })
+            })
+        );
+        let Done::Write(result) = done else {
+            unreachable!("a write finished as another operation")
+        };
+        Poll::Ready(result)
+    }
+
+    pub(crate) fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let done = ready!(self.poll(cx, Tag::Flush, |mut inner, _| {
+            Box::pin(async move {
+                let result = Write::flush(&mut inner).await;
+                (inner, Done::Flush(result))
+            })
+        }));
+        let Done::Flush(result) = done else {
+            unreachable!("a flush finished as another operation")
+        };
+        Poll::Ready(result)
+    }
+}
+
+impl<T: Seek + Send + 'static> Driver<T> {
+    pub(crate) fn poll_seek(
+        &mut self,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<std::io::Result<u64>> {
+        let done = ready!(self.poll(cx, Tag::Seek(pos), |mut inner, unread| {
+            // Relative seeks are made from the logical position, which the
+            // stream is ahead of by the unread bytes.
+            let pos = match pos {
+                SeekFrom::Current(offset) => {
+                    SeekFrom::Current(offset.saturating_sub(unread.len() as i64))
+                }
+                pos => pos,
+            };
+            unread.clear();
+            Box::pin(async move {
+                let result = inner.seek(pos).await;
+                (inner, Done::Seek(result))
+            })
+        }));
+        let Done::Seek(result) = done else {
+            unreachable!("a seek finished as another operation")
+        };
+        Poll::Ready(result)
+    }
+}
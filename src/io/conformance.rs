@@ -0,0 +1,283 @@
+//! Runs the same operations through this crate's I/O impls and through
+//! `std::io`, and checks that every result and the final contents agree.
+use std::fs::File;
+use std::io::{Cursor, ErrorKind, SeekFrom};
+
+#[derive(Debug)]
+enum Op {
+    Read(usize),
+    ReadExact(usize),
+    ReadToEnd,
+    Write(&'static [u8]),
+    WriteAll(&'static [u8]),
+    Seek(SeekFrom),
+    Position,
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Bytes(Vec<u8>),
+    Count(u64),
+    Done,
+    Failed(ErrorKind),
+}
+
+fn outcome<T>(result: std::io::Result<T>, ok: impl FnOnce(T) -> Outcome) -> Outcome {
+    result.map_or_else(|e| Outcome::Failed(e.kind()), ok)
+}
+
+/// Applies an operation with this crate's traits, or returns `None` if the
+/// operation belongs to a different trait.
+mod binrw_io {
+    use super::{Op, Outcome, outcome};
+    use crate::io::{Read, Seek, Write};
+
+    pub(super) async fn read<T: Read + Send>(s: &mut T, op: &Op) -> Option<Outcome> {
+        Some(match *op {
+            Op::Read(n) => {
+                let mut buf = vec![0; n];
+                outcome(s.read(&mut buf).await, |n| {
+                    Outcome::Bytes(buf[..n].to_vec())
+                })
+            }
+            Op::ReadExact(n) => {
+                let mut buf = vec![0; n];
+                outcome(s.read_exact(&mut buf).await, |()| Outcome::Bytes(buf))
+            }
+            Op::ReadToEnd => {
+                let mut buf = Vec::new();
+                outcome(s.read_to_end(&mut buf).await, |_| Outcome::Bytes(buf))
+            }
+            _ => return None,
+        })
+    }
+
+    pub(super) async fn write<T: Write + Send>(s: &mut T, op: &Op) -> Option<Outcome> {
+        Some(match *op {
+            Op::Write(buf) => outcome(s.write(buf).await, |n| Outcome::Count(n as u64)),
+            Op::WriteAll(buf) => outcome(s.write_all(buf).await, |()| Outcome::Done),
+            _ => return None,
+        })
+    }
+
+    pub(super) async fn seek<T: Seek + Send>(s: &mut T, op: &Op) -> Option<Outcome> {
+        Some(match *op {
+            Op::Seek(pos) => outcome(s.seek(pos).await, Outcome::Count),
+            Op::Position => outcome(s.stream_position().await, Outcome::Count),
+            _ => return None,
+        })
+    }
+}
+
+/// The same as [`binrw_io`], with `std::io`.
+mod std_io {
+    use super::{Op, Outcome, outcome};
+    use std::io::{Read, Seek, Write};
+
+    pub(super) async fn read<T: Read>(s: &mut T, op: &Op) -> Option<Outcome> {
+        Some(match *op {
+            Op::Read(n) => {
+                let mut buf = vec![0; n];
+                outcome(s.read(&mut buf), |n| Outcome::Bytes(buf[..n].to_vec()))
+            }
+            Op::ReadExact(n) => {
+                let mut buf = vec![0; n];
+                outcome(s.read_exact(&mut buf), |()| Outcome::Bytes(buf))
+            }
+            Op::ReadToEnd => {
+                let mut buf = Vec::new();
+                outcome(s.read_to_end(&mut buf), |_| Outcome::Bytes(buf))
+            }
+            _ => return None,
+        })
+    }
+
+    pub(super) async fn write<T: Write>(s: &mut T, op: &Op) -> Option<Outcome> {
+        Some(match *op {
+            Op::Write(buf) => outcome(s.write(buf), |n| Outcome::Count(n as u64)),
+            Op::WriteAll(buf) => outcome(s.write_all(buf), |()| Outcome::Done),
+            _ => return None,
+        })
+    }
+
+    pub(super) async fn seek<T: Seek>(s: &mut T, op: &Op) -> Option<Outcome> {
+        Some(match *op {
+            Op::Seek(pos) => outcome(s.seek(pos), Outcome::Count),
+            Op::Position => outcome(s.stream_position(), Outcome::Count),
+            _ => return None,
+        })
+    }
+}
+
+/// Runs `ops` against both streams using the listed traits, and evaluates to
+/// the two streams so their contents can be compared.
+macro_rules! conform {
+    ($ours:expr, $theirs:expr, [$($kind:ident),+], $ops:expr) => {{
+        let mut ours = $ours;
+        let mut theirs = $theirs;
+        for op in $ops {
+            let mut expected = None;
+            let mut actual = None;
+            $(
+                if expected.is_none() {
+                    expected = std_io::$kind(&mut theirs, op).await;
+                    actual = binrw_io::$kind(&mut ours, op).await;
+                }
+            )+
+            assert_eq!(actual, expected, "{op:?} on {}", stringify!($ours));
+        }
+        (ours, theirs)
+    }};
+}
+
+const READ_OPS: &[Op] = &[
+    Op::Read(3),
+    Op::Position,
+    Op::Seek(SeekFrom::Current(-2)),
+    Op::ReadExact(4),
+    Op::Seek(SeekFrom::End(-3)),
+    Op::Read(8),
+    Op::Read(8),
+    Op::Seek(SeekFrom::Start(100)),
+    Op::Read(1),
+    Op::Position,
+    Op::Seek(SeekFrom::Current(-200)),
+    Op::Position,
+    Op::Seek(SeekFrom::Start(2)),
+    Op::ReadExact(100),
+    Op::Position,
+    Op::Seek(SeekFrom::Start(9)),
+    Op::ReadToEnd,
+];
+
+const WRITE_OPS: &[Op] = &[
+    Op::Write(b"abc"),
+    Op::Position,
+    Op::Seek(SeekFrom::Start(1)),
+    Op::WriteAll(b"XY"),
+    Op::Seek(SeekFrom::End(-4)),
+    Op::Write(b"0123456789"),
+    Op::WriteAll(b"zz"),
+    Op::Position,
+    Op::Seek(SeekFrom::Start(20)),
+    Op::Write(b"!"),
+    Op::Seek(SeekFrom::Current(-100)),
+    Op::Position,
+];
+
+fn data() -> [u8; 16] {
+    core::array::from_fn(|i| i as u8)
+}
+
+#[tokio::test]
+async fn test_read_seek() {
+    conform!(
+        Cursor::new(data().to_vec()),
+        Cursor::new(data().to_vec()),
+        [read, seek],
+        READ_OPS
+    );
+    let (x, y) = (data(), data());
+    conform!(
+        Cursor::new(&x[..]),
+        Cursor::new(&y[..]),
+        [read, seek],
+        READ_OPS
+    );
+    conform!(
+        Cursor::new(data()),
+        Cursor::new(data()),
+        [read, seek],
+        READ_OPS
+    );
+    conform!(
+        Cursor::new(Box::<[u8]>::from(data())),
+        Cursor::new(Box::<[u8]>::from(data())),
+        [read, seek],
+        READ_OPS
+    );
+    let (mut a, mut b) = (data(), data());
+    conform!(
+        Cursor::new(&mut a[..]),
+        Cursor::new(&mut b[..]),
+        [read, seek],
+        READ_OPS
+    );
+    let (mut a, mut b) = (Cursor::new(data()), Cursor::new(data()));
+    conform!(&mut a, &mut b, [read, seek], READ_OPS);
+    conform!(&x[..], &y[..], [read], READ_OPS);
+}
+
+#[tokio::test]
+async fn test_write_seek() {
+    let (ours, theirs) = conform!(
+        Cursor::new(data().to_vec()),
+        Cursor::new(data().to_vec()),
+        [write, seek],
+        WRITE_OPS
+    );
+    assert_eq!(ours.into_inner(), theirs.into_inner());
+    let (ours, theirs) = conform!(
+        Cursor::new(data()),
+        Cursor::new(data()),
+        [write, seek],
+        WRITE_OPS
+    );
+    assert_eq!(ours.into_inner(), theirs.into_inner());
+    let (ours, theirs) = conform!(
+        Cursor::new(Box::<[u8]>::from(data())),
+        Cursor::new(Box::<[u8]>::from(data())),
+        [write, seek],
+        WRITE_OPS
+    );
+    assert_eq!(ours.into_inner(), theirs.into_inner());
+
+    let (mut a, mut b) = (data(), data());
+    conform!(
+        Cursor::new(&mut a[..]),
+        Cursor::new(&mut b[..]),
+        [write, seek],
+        WRITE_OPS
+    );
+    assert_eq!(a, b);
+    let (mut a, mut b) = (data().to_vec(), data().to_vec());
+    conform!(
+        Cursor::new(&mut a),
+        Cursor::new(&mut b),
+        [write, seek],
+        WRITE_OPS
+    );
+    assert_eq!(a, b);
+    let (mut a, mut b) = (Cursor::new(data().to_vec()), Cursor::new(data().to_vec()));
+    conform!(&mut a, &mut b, [write, seek], WRITE_OPS);
+    assert_eq!(a.into_inner(), b.into_inner());
+}
+
+#[tokio::test]
+async fn test_write_only() {
+    let (ours, theirs) = conform!(data().to_vec(), data().to_vec(), [write], WRITE_OPS);
+    assert_eq!(ours, theirs);
+    let (mut a, mut b) = ([0; 8], [0; 8]);
+    conform!(&mut a[..], &mut b[..], [write], WRITE_OPS);
+    assert_eq!(a, b);
+}
+
+#[tokio::test]
+async fn test_file() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir();
+    let paths = ["ours", "theirs"]
+        .map(|name| dir.join(format!("binrw-conformance-{name}-{}", std::process::id())));
+    let open = |path: &std::path::Path| -> std::io::Result<File> {
+        std::fs::write(path, data())?;
+        File::options().read(true).write(true).open(path)
+    };
+    let ops = READ_OPS.iter().chain(WRITE_OPS).chain(READ_OPS);
+    conform!(open(&paths[0])?, open(&paths[1])?, [read, write, seek], ops);
+    let contents = paths.each_ref().map(std::fs::read);
+    for path in &paths {
+        std::fs::remove_file(path)?;
+    }
+    let [ours, theirs] = contents;
+    assert_eq!(ours?, theirs?);
+    Ok(())
+}
@@ -0,0 +1,187 @@
+//! Adapters between this crate's streams and the `futures-io` traits used by
+//! smol, async-std and other runtimes.
+//!
+//! [`FuturesCompat`] lets a `futures-io` stream be read and written with
+//! [`BinRead`](crate::BinRead) and [`BinWrite`](crate::BinWrite).
+//! [`FuturesAdapter`] goes the other way, exposing one of this crate's
+//! streams as a `futures-io` `AsyncRead`, `AsyncWrite` and `AsyncSeek`.
+use crate::io::compat::Driver;
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+use futures_io::{AsyncRead, AsyncSeek, AsyncWrite};
+use std::future::poll_fn;
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A `futures-io` stream usable wherever this crate expects a [`Read`],
+/// [`Write`] or [`Seek`].
+pub struct FuturesCompat<T>(pub T);
+
+impl<T> FuturesCompat<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: AsyncRead + Unpin + Send> Read for FuturesCompat<T> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        poll_fn(move |cx| Pin::new(&mut self.0).poll_read(cx, buf))
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+impl<T: AsyncWrite + Unpin + Send> Write for FuturesCompat<T> {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        poll_fn(move |cx| Pin::new(&mut self.0).poll_write(cx, buf))
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        poll_fn(move |cx| Pin::new(&mut self.0).poll_flush(cx))
+    }
+}
+
+impl<T: AsyncSeek + Unpin + Send> Seek for FuturesCompat<T> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        poll_fn(move |cx| Pin::new(&mut self.0).poll_seek(cx, pos))
+    }
+}
+
+/// One of this crate's streams exposed as a `futures-io` `AsyncRead`,
+/// `AsyncWrite` and `AsyncSeek`.
+pub struct FuturesAdapter<T> {
+    driver: Driver<T>,
+}
+
+impl<T: Send + 'static> FuturesAdapter<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            driver: Driver::new(inner),
+        }
+    }
+
+    /// The inner stream, unless an operation is still in flight.
+    pub fn get_ref(&self) -> Option<&T> {
+        self.driver.get_ref()
+    }
+
+    /// Returns the inner stream, unless an operation is still in flight.
+    /// Bytes taken by a cancelled read that no later read picked up are lost.
+    pub fn into_inner(self) -> Option<T> {
+        self.driver.into_inner()
+    }
+}
+
+impl<T: Read + Send + 'static> AsyncRead for FuturesAdapter<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.get_mut().driver.poll_read(cx, buf)
+    }
+}
+
+impl<T: Write + Send + 'static> AsyncWrite for FuturesAdapter<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.get_mut().driver.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.get_mut().driver.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl<T: Seek + Send + 'static> AsyncSeek for FuturesAdapter<T> {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<std::io::Result<u64>> {
+        self.get_mut().driver.poll_seek(cx, pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinRead, BinReaderExt, BinWrite, BinWriterExt, NullString};
+    use ::futures::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, Cursor};
+    use anyhow::Result;
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(big, magic = 0xcafeu16)]
+    struct Record {
+        version: u16,
+        name: NullString,
+    }
+
+    #[test]
+    fn test_futures_cursor() -> Result<()> {
+        ::futures::executor::block_on(async {
+            let bytes = vec![0xca, 0xfe, 0, 2, b'o', b'k', 0];
+            let mut stream = FuturesCompat(Cursor::new(bytes.clone()));
+            let record = stream.read_le::<Record>().await?;
+            assert_eq!(record.version, 2);
+            assert_eq!(record.name.to_string(), "ok");
+
+            let mut out = FuturesCompat(Cursor::new(Vec::new()));
+            out.write_le(&record).await?;
+            assert_eq!(out.into_inner().into_inner(), bytes);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_futures_adapter() -> Result<()> {
+        ::futures::executor::block_on(async {
+            let mut stream = FuturesAdapter::new(std::io::Cursor::new(vec![1u8, 2, 3, 4, 5]));
+            stream.seek(SeekFrom::Start(1)).await?;
+            let mut buf = [0; 3];
+            stream.read_exact(&mut buf).await?;
+            assert_eq!(buf, [2, 3, 4]);
+            stream.write_all(&[9]).await?;
+            assert_eq!(stream.stream_position().await?, 5);
+            assert_eq!(stream.into_inner().unwrap().into_inner(), [1, 2, 3, 4, 9]);
+            Ok(())
+        })
+    }
+
+    /// A stream whose seeks are pending once before they complete.
+    struct SlowSeek(std::io::Cursor<Vec<u8>>);
+
+    impl Seek for SlowSeek {
+        fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+            async move {
+                tokio::task::yield_now().await;
+                Seek::seek(&mut self.0, pos).await
+            }
+        }
+    }
+
+    #[test]
+    fn test_futures_adapter_cancelled_seek() -> Result<()> {
+        ::futures::executor::block_on(async {
+            let mut stream = FuturesAdapter::new(SlowSeek(std::io::Cursor::new(vec![0; 8])));
+            {
+                let mut seek = std::pin::pin!(stream.seek(SeekFrom::Start(4)));
+                assert!(::futures::poll!(&mut seek).is_pending());
+            }
+            assert_eq!(stream.seek(SeekFrom::Start(1)).await?, 1);
+            assert_eq!(stream.seek(SeekFrom::Current(2)).await?, 3);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,184 @@
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::{BinResult, Error};
+use std::io::SeekFrom;
+
+/// Limits on how much memory a read may allocate up front.
+///
+/// Attach a policy to a reader with [`Limited`]. Collection reads such as
+/// `Vec<B>` consult it before allocating and fail with [`Error::TooLarge`]
+/// instead of trusting a length read from the stream.
+#[derive(Clone, Debug, Default)]
+pub struct AllocationPolicy {
+    /// The largest element count a single collection read may request.
+    pub max_count: Option<usize>,
+    /// The number of bytes all collection reads may still allocate, shared
+    /// across every read through the same reader.
+    pub byte_budget: Option<u64>,
+    /// Whether to compare element counts against the remaining stream length
+    /// before allocating, counting at least one byte per element.
+    pub check_length: bool,
+}
+
+impl AllocationPolicy {
+    /// Checks a request for `count` elements of `size` bytes each at `pos`,
+    /// and charges it against the byte budget.
+    pub fn reserve(&mut self, pos: u64, count: usize, size: usize) -> BinResult<()> {
+        if let Some(limit) = self.max_count
+            && count > limit
+        {
+            return Err(Error::TooLarge { pos, count, limit });
+        }
+        if let Some(budget) = &mut self.byte_budget {
+            let bytes = (count as u64).saturating_mul(size as u64);
+            if bytes > *budget {
+                let limit = usize::try_from(*budget / size.max(1) as u64).unwrap_or(usize::MAX);
+                return Err(Error::TooLarge { pos, count, limit });
+            }
+            *budget -= bytes;
+        }
+        Ok(())
+    }
+}
+
+/// A reader that enforces an [`AllocationPolicy`] on everything read through
+/// it.
+pub struct Limited<R> {
+    inner: R,
+    policy: AllocationPolicy,
+}
+
+impl<R> Limited<R> {
+    pub fn new(inner: R, policy: AllocationPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The policy, including whatever is left of the byte budget.
+    pub fn policy(&self) -> &AllocationPolicy {
+        &self.policy
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Send> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        self.inner.read(buf)
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.inner.flush()
+    }
+
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        Some(&mut self.policy)
+    }
+}
+
+impl<R: Seek> Seek for Limited<R> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinReaderExt;
+    use std::io::Cursor;
+
+    fn limited(len: usize, policy: AllocationPolicy) -> Limited<Cursor<Vec<u8>>> {
+        Limited::new(Cursor::new(vec![0; len]), policy)
+    }
+
+    #[tokio::test]
+    async fn test_max_count() {
+        let policy = AllocationPolicy {
+            max_count: Some(4),
+            ..Default::default()
+        };
+        let mut reader = limited(16, policy);
+        assert_eq!(reader.read_le_args::<Vec<u16>>(4).await.unwrap().len(), 4);
+        let err = reader.read_le_args::<Vec<u16>>(5).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TooLarge {
+                pos: 8,
+                count: 5,
+                limit: 4
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_byte_budget() {
+        let policy = AllocationPolicy {
+            byte_budget: Some(6),
+            ..Default::default()
+        };
+        let mut reader = limited(16, policy);
+        reader.read_le_args::<Vec<u8>>(4).await.unwrap();
+        assert_eq!(reader.policy().byte_budget, Some(2));
+        let err = reader.read_le_args::<Vec<u8>>(4).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TooLarge {
+                pos: 4,
+                count: 4,
+                limit: 2
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_length() {
+        let policy = AllocationPolicy {
+            check_length: true,
+            ..Default::default()
+        };
+        let mut reader = limited(3, policy);
+        let err = reader.read_le_args::<Vec<u8>>(1 << 40).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TooLarge {
+                pos: 0,
+                limit: 3,
+                ..
+            }
+        ));
+        let err = reader.read_le_args::<Vec<u32>>(1 << 40).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TooLarge {
+                pos: 0,
+                limit: 3,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_count_fails_at_eof() {
+        let mut reader = Cursor::new(vec![0u8; 3]);
+        let err = reader
+            .read_le_args::<Vec<u8>>(usize::MAX)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+        let err = reader
+            .read_le_args::<Vec<u32>>(usize::MAX)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+    }
+}
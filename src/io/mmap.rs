@@ -0,0 +1,162 @@
+use crate::io::read::{Read, ReadAt};
+use crate::io::seek::Seek;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{Cursor, Error, ErrorKind, SeekFrom};
+use std::path::Path;
+
+/// A memory-mapped file read as a stream.
+///
+/// Reads copy out of the mapping without any system calls. To parse without
+/// copying at all, take a [`cursor`](Self::cursor) and read
+/// [`BinReadBorrowed`](crate::BinReadBorrowed) types from it.
+pub struct MmapReader {
+    map: Mmap,
+    pos: u64,
+}
+
+impl MmapReader {
+    /// Maps the file at `path`.
+    ///
+    /// The mapping assumes the file is not truncated or modified while it is
+    /// open; that is undefined behaviour that no read through this type can
+    /// guard against.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::map(&File::open(path)?)
+    }
+
+    /// Maps `file`, with the same assumptions as [`MmapReader::open`].
+    pub fn map(file: &File) -> std::io::Result<Self> {
+        // SAFETY: the caller is told to keep the file unchanged while mapped,
+        // which is all `Mmap::map` requires.
+        let map = unsafe { Mmap::map(file)? };
+        Ok(Self::from_mmap(map))
+    }
+
+    pub fn from_mmap(map: Mmap) -> Self {
+        Self { map, pos: 0 }
+    }
+
+    /// The whole mapped file.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// A cursor over the mapped file at the current position, for reads that
+    /// borrow from the mapping.
+    pub fn cursor(&self) -> Cursor<&[u8]> {
+        let mut cursor = Cursor::new(self.as_slice());
+        cursor.set_position(self.pos);
+        cursor
+    }
+
+    pub fn into_inner(self) -> Mmap {
+        self.map
+    }
+}
+
+impl Read for MmapReader {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move {
+            let n = self.map.read_at(buf, self.pos)?;
+            self.pos += n as u64;
+            Ok(n)
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+impl Seek for MmapReader {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            let target = match pos {
+                SeekFrom::Start(target) => Some(target),
+                SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+                SeekFrom::End(offset) => (self.map.len() as u64).checked_add_signed(offset),
+            };
+            self.pos = target.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    "invalid seek to a negative or overflowing position",
+                )
+            })?;
+            Ok(self.pos)
+        }
+    }
+
+    fn stream_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send
+    where
+        Self: Send,
+    {
+        async move { Ok(self.pos) }
+    }
+}
+
+impl ReadAt for MmapReader {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.map.read_at(buf, offset)
+    }
+
+    fn size(&self) -> u64 {
+        self.map.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinReadBorrowed, BinReaderExt, Endian, NullStr};
+    use anyhow::Result;
+
+    struct Entry<'de> {
+        name: NullStr<'de>,
+        payload: &'de [u8],
+    }
+
+    impl<'de> BinReadBorrowed<'de> for Entry<'de> {
+        type Args<'a> = ();
+
+        fn read_borrowed_options(
+            reader: &mut Cursor<&'de [u8]>,
+            endian: Endian,
+            (): Self::Args<'_>,
+        ) -> impl Future<Output = crate::BinResult<Self>> + Send {
+            async move {
+                let name = NullStr::read_borrowed(reader, endian).await?;
+                let len = u32::read_borrowed(reader, endian).await?;
+                let payload = <&[u8]>::read_borrowed_options(reader, endian, len as usize).await?;
+                Ok(Self { name, payload })
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mmap_reader() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("binrw-mmap-{}", std::process::id()));
+        std::fs::write(&path, b"MM\0\x01\xffkey\0\x03\0\0\0abcrest")?;
+        let reader = MmapReader::open(&path);
+        std::fs::remove_file(&path)?;
+        let mut reader = reader?;
+
+        assert_eq!(reader.read_be::<u16>().await?, u16::from_be_bytes(*b"MM"));
+        reader.seek(SeekFrom::Current(3)).await?;
+        let mut cursor = reader.cursor();
+        let entry = Entry::read_borrowed(&mut cursor, Endian::Little).await?;
+        assert_eq!(entry.name.to_str()?, "key");
+        assert_eq!(entry.payload, b"abc");
+        assert!(core::ptr::eq(
+            entry.payload.as_ptr(),
+            &reader.as_slice()[13]
+        ));
+
+        reader.seek(SeekFrom::End(-4)).await?;
+        let mut rest = [0; 4];
+        reader.read_exact(&mut rest).await?;
+        assert_eq!(&rest, b"rest");
+        assert_eq!(reader.size(), 20);
+        Ok(())
+    }
+}
@@ -1,8 +1,38 @@
 pub mod read;
 pub mod write;
 pub mod seek;
+pub mod limit;
+pub mod buffered;
+pub mod no_seek;
+pub mod take_seek;
+pub mod read_at;
+pub mod bits;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 mod copy;
+#[cfg(test)]
+mod conformance;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod compat;
+#[cfg(feature = "futures-io")]
+pub mod futures;
+#[cfg(feature = "tokio")]
+pub mod tokio;
 pub use read::Read;
 pub use write::Write;
 pub use seek::Seek;
-pub use copy::copy;
\ No newline at end of file
+pub use std::io::SeekFrom;
+pub use limit::{AllocationPolicy, Limited};
+pub use buffered::{BufReader, BufWriter};
+pub use no_seek::NoSeek;
+pub use take_seek::TakeSeek;
+pub use read_at::ReadAtCursor;
+pub use read::ReadAt;
+pub use bits::{BitOrder, BitOverflow, BitReader, BitWriter};
+#[cfg(feature = "mmap")]
+pub use mmap::MmapReader;
+pub use copy::copy;
+#[cfg(feature = "tokio")]
+pub use self::tokio::{TokioAdapter, TokioCompat};
+#[cfg(feature = "futures-io")]
+pub use self::futures::{FuturesAdapter, FuturesCompat};
@@ -0,0 +1,148 @@
+use crate::io::limit::AllocationPolicy;
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use std::io::{Error, ErrorKind, SeekFrom};
+
+/// Lets a forward-only reader, such as a socket, a pipe or a decompressor,
+/// be parsed with [`BinRead`](crate::BinRead).
+///
+/// The position is counted as bytes are read. Seeking to the current
+/// position always succeeds, seeking forward discards bytes, and seeking
+/// backward or from the end fails with [`ErrorKind::Unsupported`].
+pub struct NoSeek<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T> NoSeek<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// The inner reader. Bytes read from it directly are not counted.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read + Send> Read for NoSeek<T> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move {
+            let n = self.inner.read(buf).await?;
+            self.pos += n as u64;
+            Ok(n)
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.inner.flush()
+    }
+
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        self.inner.allocation_policy()
+    }
+}
+
+impl<T: Read + Send> Seek for NoSeek<T> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            let target = match pos {
+                SeekFrom::Start(target) => Some(target),
+                SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+                SeekFrom::End(_) => None,
+            };
+            let Some(target) = target.filter(|&target| target >= self.pos) else {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "cannot seek backward or from the end of a forward-only stream",
+                ));
+            };
+
+            let mut scratch = [0; 1024];
+            while self.pos < target {
+                let len = scratch.len().min((target - self.pos) as usize);
+                if self.read(&mut scratch[..len]).await? == 0 {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "stream ended before the seek target",
+                    ));
+                }
+            }
+            Ok(self.pos)
+        }
+    }
+
+    fn stream_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send
+    where
+        Self: Send,
+    {
+        async move { Ok(self.pos) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinRead, BinReaderExt, Error as BinError, NullString};
+    use anyhow::Result;
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(big, magic = 0x50u8)]
+    struct Packet {
+        id: u16,
+        name: NullString,
+    }
+
+    #[tokio::test]
+    async fn test_parse_forward_only() -> Result<()> {
+        let data = [b'P', 0, 7, b'h', b'i', 0, 0xff, 0xff, b'P', 0, 8, 0];
+        let mut reader = NoSeek::new(&data[..]);
+        let first = reader.read_be::<Packet>().await?;
+        assert_eq!(first.id, 7);
+        assert_eq!(first.name.to_string(), "hi");
+        reader.seek(SeekFrom::Current(2)).await?;
+        let second = reader.read_be::<Packet>().await?;
+        assert_eq!(second.id, 8);
+        assert_eq!(reader.stream_position().await?, 12);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_seeks() -> Result<()> {
+        let data = [0u8; 8];
+        let mut reader = NoSeek::new(&data[..]);
+        reader.seek(SeekFrom::Start(4)).await?;
+        assert_eq!(reader.seek(SeekFrom::Current(0)).await?, 4);
+        for pos in [SeekFrom::Start(3), SeekFrom::Current(-1), SeekFrom::End(0)] {
+            let err = reader.seek(pos).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+        }
+        let err = reader.seek(SeekFrom::Start(9)).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_rewind_keeps_error() {
+        let data = [b'Q', 0, 0];
+        let mut reader = NoSeek::new(&data[..]);
+        let err = reader.read_be::<Packet>().await.unwrap_err();
+        let BinError::Backtrace(bt) = err else {
+            panic!("expected a backtrace, got {err:?}");
+        };
+        assert!(matches!(*bt.error, BinError::Io(ref e) if e.kind() == ErrorKind::Unsupported));
+        assert!(
+            bt.frames
+                .iter()
+                .any(|frame| format!("{frame:?}").contains("bad magic"))
+        );
+    }
+}
@@ -1,5 +1,9 @@
+use crate::io::limit::AllocationPolicy;
+use crate::io::seek::Seek;
+use crate::io::take_seek::TakeSeek;
 use std::cmp;
 use std::fs::File;
+use std::sync::Arc;
 
 pub trait Read {
     fn read_byte(&mut self) -> impl Future<Output = std::io::Result<u8>> + Send
@@ -14,6 +18,11 @@ pub trait Read {
     }
     fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send;
     fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send;
+    /// The allocation limits for reads from this stream, if any; see
+    /// [`Limited`](crate::io::Limited).
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        None
+    }
     fn read_exact(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<()>> + Send
     where
         Self: Send,
@@ -64,9 +73,22 @@ pub trait ReadExt {
         Take { inner: self, limit }
     }
 
-    // 不消耗 self 所有权，而是借用 self 的 take_ref (类似于 std::io::Read::by_ref().take())
-    // 这里的命名为了避免冲突，使用 take_borrowed 或者类似的
-    // 但更标准的做法是让 Take 支持 &mut R
+    /// A seekable window over `len` bytes of this stream starting at `start`;
+    /// see [`TakeSeek`].
+    fn take_seek(self, start: u64, len: u64) -> TakeSeek<Self>
+    where
+        Self: Seek + Sized,
+    {
+        TakeSeek::new(self, start, len)
+    }
+
+    // 借用 self，类似于 std::io::Read::by_ref()，之后可以接 take 或 take_seek
+    fn by_ref(&mut self) -> &mut Self
+    where
+        Self: Sized,
+    {
+        self
+    }
 }
 
 impl<R: Read> ReadExt for R {}
@@ -110,6 +132,10 @@ impl<R: Read + Send> Read for Take<R> {
     async fn flush(&mut self) -> std::io::Result<()> {
         self.inner.flush().await
     }
+
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        self.inner.allocation_policy()
+    }
 }
 #[cfg(test)]
 mod take_tests {
@@ -134,6 +160,10 @@ impl<R: Read + ?Sized + Send> Read for &mut R {
     async fn flush(&mut self) -> std::io::Result<()> {
         (**self).flush().await
     }
+
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        (**self).allocation_policy()
+    }
 }
 
 impl<T> Read for std::io::Cursor<T>
@@ -223,13 +253,15 @@ impl ReadAt for File {
     }
 }
 
-impl ReadAt for Vec<u8> {
+impl ReadAt for [u8] {
     fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
-        let offset = offset as usize;
+        let Ok(offset) = usize::try_from(offset) else {
+            return Ok(0);
+        };
         if offset >= self.len() {
             return Ok(0);
         }
-        let end = std::cmp::min(offset + buf.len(), self.len());
+        let end = std::cmp::min(offset.saturating_add(buf.len()), self.len());
         let slice = &self[offset..end];
         buf[..slice.len()].copy_from_slice(slice);
         Ok(slice.len())
@@ -238,3 +270,40 @@ impl ReadAt for Vec<u8> {
         self.len() as u64
     }
 }
+
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.as_slice().read_at(buf, offset)
+    }
+    fn size(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ReadAt for memmap2::Mmap {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self[..].read_at(buf, offset)
+    }
+    fn size(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for &T {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+}
@@ -0,0 +1,158 @@
+use crate::io::read::{Read, ReadAt};
+use crate::io::seek::Seek;
+use std::io::{Error, ErrorKind, SeekFrom};
+
+/// A [`Read`] and [`Seek`] stream over a shared [`ReadAt`] source, with its
+/// own position.
+///
+/// Reads go straight to [`ReadAt::read_at`], so any number of cursors can
+/// parse different regions of the same file at once without a lock. To
+/// share a source between tasks, clone an `Arc<File>` into each task and
+/// create a cursor over it there.
+pub struct ReadAtCursor<'a, T: ReadAt + ?Sized> {
+    inner: &'a T,
+    pos: u64,
+}
+
+impl<'a, T: ReadAt + ?Sized> ReadAtCursor<'a, T> {
+    pub fn new(inner: &'a T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    /// A cursor starting at `pos` instead of the beginning.
+    pub fn at(inner: &'a T, pos: u64) -> Self {
+        Self { inner, pos }
+    }
+
+    pub fn get_ref(&self) -> &'a T {
+        self.inner
+    }
+}
+
+impl<T: ReadAt + ?Sized> Clone for ReadAtCursor<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner,
+            pos: self.pos,
+        }
+    }
+}
+
+impl<T: ReadAt + ?Sized> Read for ReadAtCursor<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move {
+            let n = self.inner.read_at(buf, self.pos)?;
+            self.pos += n as u64;
+            Ok(n)
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+impl<T: ReadAt + ?Sized> Seek for ReadAtCursor<'_, T> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            let target = match pos {
+                SeekFrom::Start(target) => Some(target),
+                SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+                SeekFrom::End(offset) => self.inner.size().checked_add_signed(offset),
+            };
+            self.pos = target.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    "invalid seek to a negative or overflowing position",
+                )
+            })?;
+            Ok(self.pos)
+        }
+    }
+
+    fn stream_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send
+    where
+        Self: Send,
+    {
+        async move { Ok(self.pos) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinRead, BinReaderExt};
+    use anyhow::Result;
+    use std::fs::File;
+    use std::sync::Arc;
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(little)]
+    struct Block {
+        id: u32,
+        checksum: u16,
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_regions() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("binrw-read-at-{}", std::process::id()));
+        let bytes = (0..8u32)
+            .flat_map(|id| [id.to_le_bytes().as_slice(), &[id as u8 ^ 0xff, 0]].concat())
+            .collect::<Vec<_>>();
+        std::fs::write(&path, bytes)?;
+        let file = Arc::new(File::open(&path)?);
+
+        let tasks = (0..8u64).map(|i| {
+            let file = file.clone();
+            tokio::spawn(async move {
+                let mut cursor = ReadAtCursor::at(&*file, i * 6);
+                cursor.read_le::<Block>().await
+            })
+        });
+        let mut blocks = Vec::new();
+        for task in tasks.collect::<Vec<_>>() {
+            blocks.push(task.await?);
+        }
+        std::fs::remove_file(&path)?;
+        for (i, block) in blocks.into_iter().enumerate() {
+            let block = block?;
+            assert_eq!(block.id, i as u32);
+            assert_eq!(block.checksum, i as u16 ^ 0xff);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shared_slices() -> Result<()> {
+        let data: Arc<[u8]> = Arc::from(&[1u8, 2, 3, 4, 5][..]);
+        let mut first = ReadAtCursor::new(&data);
+        let mut second = first.clone();
+        assert_eq!(first.read_be::<u16>().await?, 0x0102);
+        second.seek(SeekFrom::End(-2)).await?;
+        assert_eq!(second.read_be::<u16>().await?, 0x0405);
+        assert!(second.read_be::<u8>().await.is_err());
+        assert_eq!(first.read_be::<u8>().await?, 3);
+
+        let slice = &data[1..];
+        let mut cursor = ReadAtCursor::new(&slice);
+        cursor.seek(SeekFrom::Current(3)).await?;
+        assert_eq!(cursor.read_be::<u8>().await?, 5);
+        let err = cursor.seek(SeekFrom::Current(-6)).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[cfg(feature = "mmap")]
+    #[tokio::test]
+    async fn test_mmap() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("binrw-read-at-mmap-{}", std::process::id()));
+        std::fs::write(&path, [0xde, 0xad, 0xbe, 0xef])?;
+        let map = unsafe { memmap2::Mmap::map(&File::open(&path)?)? };
+        let mut cursor = ReadAtCursor::new(&map);
+        let value = cursor.read_be::<u32>().await;
+        drop(map);
+        std::fs::remove_file(&path)?;
+        assert_eq!(value?, 0xdead_beef);
+        Ok(())
+    }
+}
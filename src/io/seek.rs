@@ -1,3 +1,4 @@
+use std::fs::File;
 use std::io::Cursor;
 use std::io::SeekFrom;
 
@@ -56,8 +57,31 @@ pub trait Seek {
     }
 }
 
-impl Seek for Cursor<Vec<u8>> {
+impl<T> Seek for Cursor<T>
+where
+    Cursor<T>: std::io::Seek,
+    T: Send,
+{
     async fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
         std::io::Seek::seek(self, pos)
     }
 }
+
+impl Seek for File {
+    async fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        std::io::Seek::seek(self, pos)
+    }
+}
+
+impl<S: Seek + ?Sized + Send> Seek for &mut S {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        (**self).seek(pos)
+    }
+
+    fn stream_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send
+    where
+        Self: Send,
+    {
+        (**self).stream_position()
+    }
+}
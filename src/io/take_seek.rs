@@ -0,0 +1,188 @@
+use crate::io::limit::AllocationPolicy;
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use std::io::{Error, ErrorKind, SeekFrom};
+
+/// A window over bytes `[start, start + len)` of a seekable stream, with
+/// positions counted from the start of the window.
+///
+/// Reads stop at the end of the window and [`SeekFrom::End`] is relative to
+/// it, so an embedded file can be parsed as if it were the whole stream. To
+/// keep using the inner stream afterwards, wrap a borrow of it made with
+/// [`ReadExt::by_ref`](crate::io::read::ReadExt::by_ref).
+pub struct TakeSeek<R> {
+    inner: R,
+    start: u64,
+    len: u64,
+    pos: u64,
+    /// Whether `inner` has to be moved to `start + pos` before the next read.
+    stale: bool,
+}
+
+impl<R> TakeSeek<R> {
+    pub fn new(inner: R, start: u64, len: u64) -> Self {
+        Self {
+            inner,
+            start,
+            len,
+            pos: 0,
+            stale: true,
+        }
+    }
+
+    /// The position of the window in the inner stream.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.stale = true;
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Seek + Send> Read for TakeSeek<R> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move {
+            let remaining = self.len.saturating_sub(self.pos);
+            if remaining == 0 || buf.is_empty() {
+                return Ok(0);
+            }
+            if self.stale {
+                self.inner
+                    .seek(SeekFrom::Start(self.start + self.pos))
+                    .await?;
+                self.stale = false;
+            }
+            let max = buf
+                .len()
+                .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+            let n = self.inner.read(&mut buf[..max]).await?;
+            self.pos += n as u64;
+            Ok(n)
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.inner.flush()
+    }
+
+    fn allocation_policy(&mut self) -> Option<&mut AllocationPolicy> {
+        self.inner.allocation_policy()
+    }
+}
+
+impl<R: Seek + Send> Seek for TakeSeek<R> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        async move {
+            let target = match pos {
+                SeekFrom::Start(target) => Some(target),
+                SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+                SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            };
+            let Some(target) = target else {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "invalid seek to a negative or overflowing position",
+                ));
+            };
+            // The inner stream is only moved when the next read needs it.
+            if target != self.pos {
+                self.pos = target;
+                self.stale = true;
+            }
+            Ok(target)
+        }
+    }
+
+    fn stream_position(&mut self) -> impl Future<Output = std::io::Result<u64>> + Send
+    where
+        Self: Send,
+    {
+        async move { Ok(self.pos) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read::ReadExt;
+    use crate::{BinRead, BinReaderExt, FilePtr8};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[derive(BinRead, Debug)]
+    #[br(little)]
+    struct Entry {
+        name: FilePtr8<u16>,
+        size: u8,
+    }
+
+    fn archive() -> Cursor<Vec<u8>> {
+        // A 4-byte archive header, then an 8-byte entry whose pointer is
+        // relative to the entry, then trailing bytes of the next entry.
+        Cursor::new(vec![
+            0xaa, 0xaa, 0xaa, 0xaa, 6, 3, 0, 0, 0, 0, 0x34, 0x12, 0xbb, 0xbb,
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_window_positions() -> Result<()> {
+        let mut window = TakeSeek::new(archive(), 4, 8);
+        let entry = window.read_le::<Entry>().await?;
+        assert_eq!(*entry.name, 0x1234);
+        assert_eq!(entry.size, 3);
+        assert_eq!(window.stream_position().await?, 2);
+
+        assert_eq!(window.seek(SeekFrom::End(-2)).await?, 6);
+        assert_eq!(window.read_le::<u16>().await?, 0x1234);
+        assert!(window.read_le::<u8>().await.is_err());
+        assert_eq!(window.stream_position().await?, 8);
+        assert_eq!(window.length().await?, 8);
+
+        let err = window.seek(SeekFrom::Current(-9)).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reads_are_clamped() -> Result<()> {
+        let mut window = TakeSeek::new(archive(), 10, 2);
+        let mut buf = Vec::new();
+        assert_eq!(window.read_to_end(&mut buf).await?, 2);
+        assert_eq!(buf, [0x34, 0x12]);
+        window.seek(SeekFrom::Start(5)).await?;
+        assert_eq!(window.read(&mut [0; 4]).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_borrowed_window() -> Result<()> {
+        let mut archive = archive();
+        let size = {
+            let mut window = archive.by_ref().take_seek(4, 8);
+            window.seek(SeekFrom::Start(1)).await?;
+            window.read_le::<u8>().await?
+        };
+        assert_eq!(size, 3);
+        assert_eq!(archive.stream_position().await?, 6);
+        assert_eq!(archive.read_le::<u16>().await?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+//! Adapters between this crate's streams and tokio's I/O traits.
+//!
+//! [`TokioCompat`] lets a tokio stream such as `tokio::fs::File` be read and
+//! written with [`BinRead`](crate::BinRead) and [`BinWrite`](crate::BinWrite).
+//! [`TokioAdapter`] goes the other way, exposing one of this crate's streams
+//! as a tokio `AsyncRead`, `AsyncWrite` and `AsyncSeek`.
+use crate::io::compat::Driver;
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+use ::tokio::io::{
+    AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt, ReadBuf,
+};
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+/// A tokio stream usable wherever this crate expects a [`Read`], [`Write`]
+/// or [`Seek`].
+pub struct TokioCompat<T>(pub T);
+
+impl<T> TokioCompat<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: AsyncRead + Unpin + Send> Read for TokioCompat<T> {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move { self.0.read(buf).await }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            self.0.read_exact(buf).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin + Send> Write for TokioCompat<T> {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        async move { self.0.write(buf).await }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.0.flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move { self.0.write_all(buf).await }
+    }
+}
+
+impl<T: AsyncSeek + Unpin + Send> Seek for TokioCompat<T> {
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+        self.0.seek(pos)
+    }
+}
+
+/// One of this crate's streams exposed as a tokio `AsyncRead`, `AsyncWrite`
+/// and `AsyncSeek`.
+pub struct TokioAdapter<T> {
+    driver: Driver<T>,
+    seek: Option<SeekFrom>,
+}
+
+impl<T: Send + 'static> TokioAdapter<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            driver: Driver::new(inner),
+            seek: None,
+        }
+    }
+
+    /// The inner stream, unless an operation is still in flight.
+    pub fn get_ref(&self) -> Option<&T> {
+        self.driver.get_ref()
+    }
+
+    /// Returns the inner stream, unless an operation is still in flight.
+    /// Bytes taken by a cancelled read that no later read picked up are lost.
+    pub fn into_inner(self) -> Option<T> {
+        self.driver.into_inner()
+    }
+}
+
+impl<T: Read + Send + 'static> AsyncRead for TokioAdapter<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let n = ready!(
+            self.get_mut()
+                .driver
+                .poll_read(cx, buf.initialize_unfilled())
+        )?;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: Write + Send + 'static> AsyncWrite for TokioAdapter<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.get_mut().driver.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.get_mut().driver.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl<T: Seek + Send + 'static> AsyncSeek for TokioAdapter<T> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let this = self.get_mut();
+        if this.seek.is_some() {
+            return Err(std::io::Error::other(
+                "a seek is still in progress; call poll_complete first",
+            ));
+        }
+        this.seek = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        let this = self.get_mut();
+        let pos = this.seek.unwrap_or(SeekFrom::Current(0));
+        let result = ready!(this.driver.poll_seek(cx, pos));
+        this.seek = None;
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinReaderExt, BinWriterExt, NullString};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn test_tokio_file() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("binrw-tokio-{}", std::process::id()));
+        let mut file = TokioCompat(tokio::fs::File::create(&path).await?);
+        file.write_be(&0x1234_5678u32).await?;
+        file.write_le(&NullString::from("tokio")).await?;
+        file.0.sync_all().await?;
+
+        let mut file = TokioCompat(tokio::fs::File::open(&path).await?);
+        let value = file.read_be::<u32>().await;
+        let name = file.read_le::<NullString>().await;
+        tokio::fs::remove_file(&path).await?;
+        assert_eq!(value?, 0x1234_5678);
+        assert_eq!(name?.to_string(), "tokio");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tokio_adapter() -> Result<()> {
+        let mut stream = TokioAdapter::new(Cursor::new(vec![1u8, 2, 3, 4, 5]));
+        stream.seek(SeekFrom::Start(1)).await?;
+        let mut buf = [0; 3];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(buf, [2, 3, 4]);
+        stream.write_all(&[9]).await?;
+        assert_eq!(stream.stream_position().await?, 5);
+        assert_eq!(stream.into_inner().unwrap().into_inner(), [1, 2, 3, 4, 9]);
+        Ok(())
+    }
+
+    /// A stream whose operations are pending once before they complete.
+    struct Slow(Cursor<Vec<u8>>);
+
+    impl Read for Slow {
+        fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+            async move {
+                tokio::task::yield_now().await;
+                Read::read(&mut self.0, buf).await
+            }
+        }
+
+        fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+            async { Ok(()) }
+        }
+    }
+
+    impl Write for Slow {
+        fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+            async move {
+                tokio::task::yield_now().await;
+                Write::write(&mut self.0, buf).await
+            }
+        }
+
+        fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
+            async { Ok(()) }
+        }
+    }
+
+    impl Seek for Slow {
+        fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = std::io::Result<u64>> + Send {
+            async move {
+                tokio::task::yield_now().await;
+                Seek::seek(&mut self.0, pos).await
+            }
+        }
+    }
+
+    /// Starts a read of `len` bytes and cancels it while it is pending.
+    async fn cancel_read(stream: &mut TokioAdapter<Slow>, len: usize) {
+        let mut buf = vec![0; len];
+        let mut read = std::pin::pin!(stream.read(&mut buf));
+        assert!(futures::poll!(&mut read).is_pending());
+    }
+
+    #[tokio::test]
+    async fn test_tokio_adapter_cancelled_read() -> Result<()> {
+        let data = vec![1u8, 2, 3, 4, 5, 6];
+        let mut stream = TokioAdapter::new(Slow(Cursor::new(data.clone())));
+        cancel_read(&mut stream, 4).await;
+        assert_eq!(stream.stream_position().await?, 0);
+        let mut buf = [0; 6];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6]);
+
+        // A retry with a smaller buffer keeps the rest for the next read.
+        let mut stream = TokioAdapter::new(Slow(Cursor::new(data.clone())));
+        cancel_read(&mut stream, 4).await;
+        let mut buf = [0; 2];
+        assert_eq!(stream.read(&mut buf).await?, 2);
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(stream.read(&mut buf).await?, 2);
+        assert_eq!(buf, [3, 4]);
+
+        // Writing would skip the unread bytes, so it fails until they are read.
+        let mut stream = TokioAdapter::new(Slow(Cursor::new(data)));
+        cancel_read(&mut stream, 2).await;
+        assert!(stream.write_all(&[9]).await.is_err());
+        let mut buf = [0; 2];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(buf, [1, 2]);
+        stream.write_all(&[9]).await?;
+        let inner = stream.into_inner().unwrap().0.into_inner();
+        assert_eq!(inner, [1, 2, 9, 4, 5, 6]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tokio_adapter_cancelled_write() -> Result<()> {
+        let mut stream = TokioAdapter::new(Slow(Cursor::new(Vec::new())));
+        {
+            let mut write = std::pin::pin!(stream.write(b"AAAA"));
+            assert!(futures::poll!(&mut write).is_pending());
+        }
+        // The cancelled write still finishes, but its result is not handed
+        // to the next one.
+        assert_eq!(stream.write(b"BB").await?, 2);
+        let inner = stream.into_inner().unwrap().0.into_inner();
+        assert_eq!(inner, b"AAAABB");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tokio_adapter_cancelled_seek() -> Result<()> {
+        let mut stream = TokioAdapter::new(Slow(Cursor::new(vec![1u8, 2, 3, 4, 5])));
+        {
+            let mut seek = std::pin::pin!(stream.seek(SeekFrom::Start(4)));
+            assert!(futures::poll!(&mut seek).is_pending());
+        }
+        assert_eq!(stream.seek(SeekFrom::Start(1)).await?, 1);
+        assert_eq!(stream.read_u8().await?, 2);
+
+        Pin::new(&mut stream).start_seek(SeekFrom::Start(3))?;
+        assert!(
+            Pin::new(&mut stream)
+                .start_seek(SeekFrom::Start(0))
+                .is_err()
+        );
+        assert_eq!(
+            std::future::poll_fn(|cx| Pin::new(&mut stream).poll_complete(cx)).await?,
+            3
+        );
+        Ok(())
+    }
+}
@@ -24,16 +24,24 @@ pub trait Write {
         }
     }
 }
-impl Write for &mut Cursor<Vec<u8>> {
+impl<W: Write + ?Sized + Send> Write for &mut W {
     fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
-        async move { std::io::Write::write(self, buf) }
+        (**self).write(buf)
     }
 
     fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
-        async { std::io::Write::flush(self) }
+        (**self).flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        (**self).write_all(buf)
     }
 }
-impl Write for Cursor<Vec<u8>> {
+impl<T> Write for Cursor<T>
+where
+    Cursor<T>: std::io::Write,
+    T: Send,
+{
     fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
         async { std::io::Write::write(self, buf) }
     }
@@ -55,22 +63,21 @@ impl Write for File {
         async { std::io::Write::flush(self) }
     }
 }
-impl Write for [u8] {
+// 和 std 一样，写满之后切片会缩短，剩余空间不足时只写入一部分
+impl Write for &mut [u8] {
     fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
-        async move {
-            self.copy_from_slice(buf);
-            Ok(buf.len())
-        }
+        async move { std::io::Write::write(self, buf) }
     }
 
     fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
         async { Ok(()) }
     }
 }
+// 和 std 一样追加到末尾
 impl Write for Vec<u8> {
     fn write(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
         async move {
-            self.copy_from_slice(buf);
+            self.extend_from_slice(buf);
             Ok(buf.len())
         }
     }
@@ -78,6 +85,13 @@ impl Write for Vec<u8> {
     fn flush(&mut self) -> impl Future<Output = std::io::Result<()>> + Send {
         async move { Ok(()) }
     }
+
+    fn write_all(&mut self, buf: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            self.extend_from_slice(buf);
+            Ok(())
+        }
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -90,10 +104,10 @@ mod tests {
         let mut data = Cursor::new(vec![4, 5, 6]);
         let mut buffer = vec![1, 2, 3];
         crate::io::copy(&mut data, &mut buffer).await?;
-        assert_eq!(buffer, vec![4, 5, 6]);
+        assert_eq!(buffer, vec![1, 2, 3, 4, 5, 6]);
         data.seek(SeekFrom::Start(3)).await?;
         crate::io::copy(&mut data, &mut buffer).await?;
-        assert_eq!(buffer, vec![4, 5, 6]);
+        assert_eq!(buffer, vec![1, 2, 3, 4, 5, 6]);
         Ok(())
     }
 }
@@ -1,13 +1,17 @@
 extern crate core;
+extern crate self as binrw;
 
-pub trait Required {
-    fn args() -> Self;
-}
+pub use private::Required;
+
+/// Argument types that can be assembled field by field through a builder.
+///
+/// Usually implemented with `#[derive(NamedArgs)]` or generated by
+/// `#[br(import { ... })]`; `finalize` on the builder only compiles once every
+/// required argument has been given.
+pub trait NamedArgs {
+    type Builder;
 
-impl<T: Default> Required for T {
-    fn args() -> Self {
-        <Self as Default>::default()
-    }
+    fn builder() -> Self::Builder;
 }
 
 pub mod read;
@@ -18,9 +22,18 @@ pub mod write;
 pub mod io;
 pub(crate) mod backtrace;
 pub mod ext;
+pub mod file_ptr;
+pub mod blocking;
+pub mod magic;
+pub mod padding;
 
 pub use error::*;
 pub use endian::*;
 pub use read::*;
 pub use write::*;
-pub use ext::strings::*;
\ No newline at end of file
+pub use ext::strings::*;
+pub use ext::encoding::TextEncoding;
+pub use file_ptr::*;
+pub use blocking::{BinReadSync, BinWriteSync};
+pub use magic::{Magic, read_magic, write_magic};
+pub use binrw_derive::{BinRead, BinWrite, NamedArgs, binrw, bitfield};
\ No newline at end of file
@@ -0,0 +1,192 @@
+//! Magic numbers: fixed values that must appear at a point in the stream.
+//!
+//! Derived types check and emit them with `#[brw(magic = ...)]`; hand-written
+//! impls use [`read_magic`] and [`write_magic`]. Either way a magic is an
+//! integer, laid out in the active [`Endian`], or a byte array such as
+//! `*b"\x7fELF"`, which is the same in both.
+use crate::io::read::Read;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+use crate::{BinResult, Endian, Error};
+use core::fmt;
+
+/// A value that can be used as a magic number.
+pub trait Magic: Sized + Send + Sync + 'static {
+    /// The encoded form of the value.
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Send;
+
+    fn to_bytes(&self, endian: Endian) -> Self::Bytes;
+
+    fn from_bytes(bytes: Self::Bytes, endian: Endian) -> Self;
+
+    /// Formats the value in hex, for error messages.
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+macro_rules! magic_int_impl {
+    ($($ty:ty),*) => {
+        $(
+            impl Magic for $ty {
+                type Bytes = [u8; size_of::<$ty>()];
+
+                fn to_bytes(&self, endian: Endian) -> Self::Bytes {
+                    match endian {
+                        Endian::Big => self.to_be_bytes(),
+                        Endian::Little => self.to_le_bytes(),
+                    }
+                }
+
+                fn from_bytes(bytes: Self::Bytes, endian: Endian) -> Self {
+                    match endian {
+                        Endian::Big => Self::from_be_bytes(bytes),
+                        Endian::Little => Self::from_le_bytes(bytes),
+                    }
+                }
+
+                fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "{:#0width$x}", self, width = 2 + 2 * size_of::<$ty>())
+                }
+            }
+        )*
+    };
+}
+
+magic_int_impl!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl<const N: usize> Magic for [u8; N] {
+    type Bytes = Self;
+
+    fn to_bytes(&self, _: Endian) -> Self::Bytes {
+        *self
+    }
+
+    fn from_bytes(bytes: Self::Bytes, _: Endian) -> Self {
+        bytes
+    }
+
+    /// Formats as `b"\x7fELF" (7f 45 4c 46)`.
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b\"{}\" (", self.escape_ascii())?;
+        for (i, byte) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Shows a magic value in hex, as it appears in [`Error::BadMagic`].
+pub(crate) struct Hex<M>(pub(crate) M);
+
+impl<M: Magic> fmt::Debug for Hex<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_hex(f)
+    }
+}
+
+/// Reads a magic number, failing with [`Error::BadMagic`] if the stream
+/// holds anything else. The reader is left after the magic either way.
+pub fn read_magic<R, M>(
+    reader: &mut R,
+    expected: M,
+    endian: Endian,
+) -> impl Future<Output = BinResult<()>> + Send
+where
+    R: Read + Seek + Send,
+    M: Magic,
+{
+    async move {
+        let pos = reader.stream_position().await?;
+        let expected_bytes = expected.to_bytes(endian);
+        let mut found = expected.to_bytes(endian);
+        reader.read_exact(found.as_mut()).await?;
+        if found.as_ref() == expected_bytes.as_ref() {
+            Ok(())
+        } else {
+            Err(Error::BadMagic {
+                pos,
+                expected: Box::new(Hex(expected)),
+                found: Box::new(Hex(M::from_bytes(found, endian))),
+            })
+        }
+    }
+}
+
+/// Writes a magic number.
+pub fn write_magic<W, M>(
+    writer: &mut W,
+    magic: M,
+    endian: Endian,
+) -> impl Future<Output = BinResult<()>> + Send
+where
+    W: Write + Seek + Send,
+    M: Magic,
+{
+    async move {
+        writer.write_all(magic.to_bytes(endian).as_ref()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(magic = b"\x7fELF")]
+    struct Elf {
+        #[brw(magic = 0x0102u16)]
+        class: u8,
+    }
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    enum Entry {
+        #[brw(magic = b"PK\x03\x04")]
+        Local(u8),
+        #[brw(magic = b"PK\x01\x02")]
+        Central(u8),
+    }
+
+    #[tokio::test]
+    async fn test_byte_string_magic() -> Result<()> {
+        let mut out = Cursor::new(Vec::new());
+        out.write_le(&Elf { class: 2 }).await?;
+        out.write_be(&Elf { class: 1 }).await?;
+        assert_eq!(out.get_ref(), b"\x7fELF\x02\x01\x02\x7fELF\x01\x02\x01");
+        out.set_position(0);
+        assert_eq!(out.read_le::<Elf>().await?, Elf { class: 2 });
+        assert_eq!(out.read_be::<Elf>().await?, Elf { class: 1 });
+
+        let mut out = Cursor::new(Vec::new());
+        out.write_le(&Entry::Central(7)).await?;
+        assert_eq!(out.get_ref(), b"PK\x01\x02\x07");
+        out.set_position(0);
+        assert_eq!(out.read_le::<Entry>().await?, Entry::Central(7));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bad_magic_message() -> Result<()> {
+        let mut data = Cursor::new(b"\x7fELG\x02\x01\x02".to_vec());
+        let err = data.read_le::<Elf>().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad magic at 0x0: expected b\"\\x7fELF\" (7f 45 4c 46), found b\"\\x7fELG\" (7f 45 4c 47)"
+        );
+
+        let mut data = Cursor::new(vec![0x4d, 0x5b]);
+        let err = read_magic(&mut data, 0x4d5au16, Endian::Big)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad magic at 0x0: expected 0x4d5a, found 0x4d5b"
+        );
+        Ok(())
+    }
+}
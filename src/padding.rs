@@ -0,0 +1,185 @@
+//! Padding, alignment and size padding around values in a stream.
+//!
+//! Derived types use these through `pad_before`, `pad_after`, `align_before`,
+//! `align_after` and `pad_size_to`. Each helper comes in a read form, which
+//! skips over the bytes, and a write form, which emits them as `fill`.
+use crate::BinResult;
+use crate::io::seek::Seek;
+use crate::io::write::Write;
+
+/// The number of bytes needed to move from `pos` to the next multiple of
+/// `align`. An `align` of zero or one never needs padding.
+pub fn padding_for(pos: u64, align: u64) -> u64 {
+    match align {
+        0 | 1 => 0,
+        align => (align - pos % align) % align,
+    }
+}
+
+/// Skips `count` bytes of padding.
+pub fn read_padding<R: Seek + Send>(
+    reader: &mut R,
+    count: u64,
+) -> impl Future<Output = BinResult<()>> + Send {
+    async move {
+        if count > 0 {
+            let count = i64::try_from(count).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "padding too large")
+            })?;
+            reader.seek_relative(count).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `count` copies of `fill`.
+pub fn write_padding<W: Write + Send>(
+    writer: &mut W,
+    count: u64,
+    fill: u8,
+) -> impl Future<Output = BinResult<()>> + Send {
+    async move {
+        if fill == 0 {
+            return crate::private::write_zeroes(writer, count).await;
+        }
+        const BUF_SIZE: u64 = 0x20;
+        let buf = [fill; BUF_SIZE as usize];
+        let mut left = count;
+        while left > 0 {
+            let len = left.min(BUF_SIZE);
+            // Lint: `len` is at most BUF_SIZE
+            #[allow(clippy::cast_possible_truncation)]
+            writer.write_all(&buf[..len as usize]).await?;
+            left -= len;
+        }
+        Ok(())
+    }
+}
+
+/// Skips to the next multiple of `align`.
+pub fn read_align<R: Seek + Send>(
+    reader: &mut R,
+    align: u64,
+) -> impl Future<Output = BinResult<()>> + Send {
+    async move {
+        let pos = reader.stream_position().await?;
+        read_padding(reader, padding_for(pos, align)).await
+    }
+}
+
+/// Fills up to the next multiple of `align`.
+pub fn write_align<W: Write + Seek + Send>(
+    writer: &mut W,
+    align: u64,
+    fill: u8,
+) -> impl Future<Output = BinResult<()>> + Send {
+    async move {
+        let pos = writer.stream_position().await?;
+        write_padding(writer, padding_for(pos, align), fill).await
+    }
+}
+
+/// Skips to `size` bytes past `start`, if the stream is not already there.
+pub fn read_pad_to<R: Seek + Send>(
+    reader: &mut R,
+    start: u64,
+    size: u64,
+) -> impl Future<Output = BinResult<()>> + Send {
+    async move {
+        let pos = reader.stream_position().await?;
+        read_padding(reader, start.saturating_add(size).saturating_sub(pos)).await
+    }
+}
+
+/// Fills up to `size` bytes past `start`, if the stream is not already there.
+pub fn write_pad_to<W: Write + Seek + Send>(
+    writer: &mut W,
+    start: u64,
+    size: u64,
+    fill: u8,
+) -> impl Future<Output = BinResult<()>> + Send {
+    async move {
+        let pos = writer.stream_position().await?;
+        write_padding(writer, start.saturating_add(size).saturating_sub(pos), fill).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::SeekFrom;
+    use crate::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(little)]
+    struct Entry {
+        tag: u8,
+        #[brw(align_before = 4)]
+        value: u32,
+        #[brw(pad_size_to = 4, pad_byte = 0xee)]
+        name: [u8; 2],
+        #[brw(pad_before = 1, align_after = 8)]
+        flags: u8,
+        #[brw(seek_before = SeekFrom::Start(1), restore_position)]
+        peek: u8,
+        last: u8,
+    }
+
+    #[tokio::test]
+    async fn test_padding_helpers() -> Result<()> {
+        assert_eq!(padding_for(5, 4), 3);
+        assert_eq!(padding_for(8, 4), 0);
+        assert_eq!(padding_for(7, 0), 0);
+
+        let mut data = Cursor::new(Vec::new());
+        write_padding(&mut data, 2, 0).await?;
+        write_align(&mut data, 8, 0xaa).await?;
+        let start = data.position();
+        data.write_le(&1u8).await?;
+        write_pad_to(&mut data, start, 3, 0xbb).await?;
+        assert_eq!(
+            data.get_ref(),
+            &[0, 0, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 1, 0xbb, 0xbb]
+        );
+
+        data.set_position(1);
+        read_align(&mut data, 4).await?;
+        assert_eq!(data.position(), 4);
+        read_pad_to(&mut data, 2, 7).await?;
+        assert_eq!(data.position(), 9);
+        read_padding(&mut data, 2).await?;
+        assert_eq!(data.position(), 11);
+
+        let err = read_pad_to(&mut data, u64::MAX, 1).await.unwrap_err();
+        assert!(matches!(err, crate::Error::Io(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_layout_directives() -> Result<()> {
+        let entry = Entry {
+            tag: 7,
+            value: 0x0102_0304,
+            name: *b"ab",
+            flags: 9,
+            peek: 0,
+            last: 5,
+        };
+        let mut data = Cursor::new(Vec::new());
+        data.write_le(&entry).await?;
+        // The `peek` write lands on the zero padding after `tag` and the
+        // stream then returns to the end for `last`.
+        assert_eq!(
+            data.get_ref(),
+            &[
+                7, 0, 0, 0, 4, 3, 2, 1, b'a', b'b', 0xee, 0xee, 0, 9, 0, 0, 5
+            ]
+        );
+
+        data.set_position(0);
+        assert_eq!(data.read_le::<Entry>().await?, entry);
+        Ok(())
+    }
+}
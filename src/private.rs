@@ -1,4 +1,9 @@
 extern crate alloc;
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no default value",
+    label = "these arguments must be passed explicitly",
+    note = "use a `*_args` method such as `read_le_args`, or pass them to the field with `args`"
+)]
 pub trait Required: MissingArgsDirective {
     fn args() -> Self;
 }
@@ -9,9 +14,30 @@ impl<T: Default> Required for T {
     }
 }
 
+#[diagnostic::on_unimplemented(
+    message = "missing `args` directive for arguments of type `{Self}`",
+    note = "pass them to the field with `args(...)`, `args {{ ... }}` or `args_raw = ...`"
+)]
 pub trait MissingArgsDirective {}
 impl<T: Default> MissingArgsDirective for T {}
 
+/// Builder state of a named argument that has not been set yet.
+pub struct Needed;
+
+/// Builder state of a named argument that has been set.
+pub struct Satisfied;
+
+/// Builder state of a named argument that falls back to its default.
+pub struct Optional;
+
+#[diagnostic::on_unimplemented(
+    message = "a required named argument has not been set",
+    label = "call the setter for every required argument before `finalize`"
+)]
+pub trait SatisfiedOrOptional {}
+impl SatisfiedOrOptional for Satisfied {}
+impl SatisfiedOrOptional for Optional {}
+
 pub enum AssertErrorFn<M, E> {
     Message(M),
     Error(E),
@@ -51,29 +77,7 @@ where
     f
 }
 
-pub async fn magic<R, B>(reader: &mut R, expected: B, endian: Endian) -> BinResult<()>
-where
-    B: for<'a> BinRead<Args<'a> = ()>
-        + core::fmt::Debug
-        + PartialEq
-        + Sync
-        + Send
-        + Clone
-        + Copy
-        + 'static,
-    R: Read + Seek + Send,
-{
-    let pos = reader.stream_position().await?;
-    let val = B::read_options(reader, endian, ()).await?;
-    if val == expected {
-        Ok(())
-    } else {
-        Err(Error::BadMagic {
-            pos,
-            found: Box::new(val) as _,
-        })
-    }
-}
+pub use crate::magic::{read_magic as magic, write_magic};
 
 #[must_use]
 pub fn not_enough_bytes() -> Error {
@@ -83,10 +87,11 @@ pub fn not_enough_bytes() -> Error {
     ))
 }
 
-pub fn parse_fn_type_hint<Ret, ParseFn, R, Args>(f: ParseFn) -> ParseFn
+pub fn parse_fn_type_hint<'r, Ret, ParseFn, R, Args, Fut>(f: ParseFn) -> ParseFn
 where
-    R: Read + Seek,
-    ParseFn: FnOnce(&mut R, Endian, Args) -> BinResult<Ret>,
+    R: Read + Seek + 'r,
+    ParseFn: FnOnce(&'r mut R, Endian, Args) -> Fut,
+    Fut: Future<Output = BinResult<Ret>>,
 {
     f
 }
@@ -115,6 +120,132 @@ where
     args
 }
 
+pub fn read_bits<R, T>(
+    reader: &mut R,
+    bits: &mut BitUnpacker,
+    width: u32,
+) -> impl Future<Output = BinResult<T>> + Send
+where
+    R: Read + Seek + Send,
+    T: BitValue,
+{
+    async move {
+        let value = crate::io::bits::read_bits(reader, bits, width).await?;
+        T::from_bits(value).ok_or_else(|| {
+            Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{value} is out of range for the field type"),
+            ))
+        })
+    }
+}
+
+pub fn write_bits<'a, W, T>(
+    writer: &'a mut W,
+    bits: &'a mut BitPacker,
+    value: &T,
+    width: u32,
+) -> impl Future<Output = BinResult<()>> + Send + 'a
+where
+    W: Write + Seek + Send,
+    T: BitValue,
+{
+    let value = value.to_bits();
+    async move { Ok(crate::io::bits::write_bits(writer, bits, value, width).await?) }
+}
+
+pub fn align_bits<W: Write + Seek + Send>(
+    writer: &mut W,
+    bits: &mut BitPacker,
+) -> impl Future<Output = BinResult<()>> + Send {
+    async move { Ok(crate::io::bits::align_bits(writer, bits).await?) }
+}
+
+/// Where a bitfield member of `width` bits starts, `offset` bits into the
+/// fields of an integer of `total` bits.
+pub const fn bitfield_shift(order: BitOrder, total: u32, offset: u32, width: u32) -> u32 {
+    match order {
+        BitOrder::MsbFirst => total - offset - width,
+        BitOrder::LsbFirst => offset,
+    }
+}
+
+pub fn bitfield_get<Repr: BitValue, T: BitValue>(raw: &Repr, shift: u32, width: u32) -> T {
+    let bits = (raw.to_bits() >> shift) & mask(width);
+    T::from_bits(bits).expect("bitfield member is no wider than its type")
+}
+
+pub fn bitfield_set<Repr: BitValue, T: BitValue>(
+    raw: &mut Repr,
+    shift: u32,
+    width: u32,
+    value: &T,
+) -> Result<(), BitOverflow> {
+    let value = value.to_bits();
+    BitOverflow::check(value, width)?;
+    let bits = (raw.to_bits() & !(mask(width) << shift)) | (value << shift);
+    *raw = Repr::from_bits(bits).expect("bitfield members fit in the integer");
+    Ok(())
+}
+
+pub fn read_map<R, MapFn, Input, Output>(
+    reader: &mut R,
+    endian: Endian,
+    map: MapFn,
+    args: Input::Args<'_>,
+) -> impl Future<Output = BinResult<Output>> + Send
+where
+    R: Read + Seek + Send,
+    MapFn: FnOnce(Input) -> Output + Send,
+    Input: BinRead + Send,
+{
+    async move { Input::read_options(reader, endian, args).await.map(map) }
+}
+
+pub fn read_try_map<R, MapFn, Input, Output, Err>(
+    reader: &mut R,
+    endian: Endian,
+    map: MapFn,
+    args: Input::Args<'_>,
+) -> impl Future<Output = BinResult<Output>> + Send
+where
+    R: Read + Seek + Send,
+    MapFn: FnOnce(Input) -> Result<Output, Err> + Send,
+    Input: BinRead + Send,
+    Err: CustomError + 'static,
+{
+    async move {
+        let pos = reader.stream_position().await?;
+        let input = Input::read_options(reader, endian, args).await?;
+        map(input).map_err(|err| custom_error(pos, err))
+    }
+}
+
+pub fn write_try_map_args_type_hint<Input, Output, Err, MapFn, Args>(
+    _: &MapFn,
+    args: Args,
+) -> Args
+where
+    Err: CustomError,
+    MapFn: FnOnce(Input) -> Result<Output, Err>,
+    Output: for<'a> BinWrite<Args<'a> = Args>,
+{
+    args
+}
+
+pub fn custom_error<Err: CustomError + 'static>(pos: u64, err: Err) -> Error {
+    Error::Custom {
+        pos,
+        err: Box::new(err),
+    }
+}
+
+/// Converts a `count` directive to the length of a `Vec`. A count that does
+/// not fit, such as a negative one, becomes too large to read instead.
+pub fn count<N: TryInto<usize>>(count: N) -> usize {
+    count.try_into().unwrap_or(usize::MAX)
+}
+
 pub fn map_reader_type_hint<'a, Reader, MapFn, Output>(x: MapFn) -> MapFn
 where
     Reader: Read + Seek + 'a,
@@ -133,10 +264,12 @@ where
     x
 }
 
-pub fn write_fn_type_hint<T, WriterFn, Writer, Args>(x: WriterFn) -> WriterFn
+pub fn write_fn_type_hint<'w, T, WriterFn, Writer, Args, Fut>(x: WriterFn) -> WriterFn
 where
-    Writer: Write + Seek,
-    WriterFn: FnOnce(&T, &mut Writer, Endian, Args) -> BinResult<()>,
+    T: 'w,
+    Writer: Write + Seek + 'w,
+    WriterFn: FnOnce(&'w T, &'w mut Writer, Endian, Args) -> Fut,
+    Fut: Future<Output = BinResult<()>>,
 {
     x
 }
@@ -193,18 +326,7 @@ use crate::{BinResult, BinWrite, CustomError, Endian, Error};
 use crate::io::read::Read;
 use crate::io::seek::Seek;
 use crate::io::write::Write;
-// pub fn write_try_map_args_type_hint<Input, Output, Error, MapFn, Args>(
-//     _: &MapFn,
-//     args: Args,
-// ) -> Args
-// where
-//     Error: CustomError,
-//     MapFn: FnOnce(Input) -> Result<Output, Error>,
-//     Output: for<'a> BinWrite<Args<'a> = Args>,
-// {
-//     args
-// }
-//
+use crate::io::bits::{BitOrder, BitOverflow, BitPacker, BitUnpacker, BitValue, mask};
 // pub fn write_map_fn_input_type_hint<Input, Output, MapFn>(func: MapFn) -> MapFn
 // where
 //     MapFn: FnOnce(Input) -> Output,
@@ -0,0 +1,123 @@
+//! Reading values that borrow from an in-memory buffer instead of copying
+//! out of it.
+use crate::{BinRead, BinResult, Endian, Error, Required};
+use std::io::{Cursor, ErrorKind};
+
+/// A type that can be read from a byte buffer that outlives it, borrowing
+/// from the buffer where it can.
+///
+/// Every [`BinRead`] type reads the same way here. Types such as `&'de [u8]`
+/// and [`NullStr`](crate::NullStr) point into the buffer instead, which with
+/// the cursor from `MmapReader::cursor` (behind the `mmap` feature) means
+/// pointing straight into the mapped file.
+pub trait BinReadBorrowed<'de>: Sized {
+    type Args<'a>: Send;
+
+    fn read_borrowed_options(
+        reader: &mut Cursor<&'de [u8]>,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send;
+
+    fn read_borrowed(
+        reader: &mut Cursor<&'de [u8]>,
+        endian: Endian,
+    ) -> impl Future<Output = BinResult<Self>> + Send
+    where
+        for<'a> Self::Args<'a>: Required,
+    {
+        Self::read_borrowed_options(reader, endian, Self::Args::args())
+    }
+}
+
+impl<'de, T: BinRead + Send> BinReadBorrowed<'de> for T {
+    type Args<'a> = T::Args<'a>;
+
+    fn read_borrowed_options(
+        reader: &mut Cursor<&'de [u8]>,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send {
+        T::read_options(reader, endian, args)
+    }
+}
+
+/// Reads `count` bytes without copying them.
+impl<'de> BinReadBorrowed<'de> for &'de [u8] {
+    type Args<'a> = usize;
+
+    fn read_borrowed_options(
+        reader: &mut Cursor<&'de [u8]>,
+        _endian: Endian,
+        count: Self::Args<'_>,
+    ) -> impl Future<Output = BinResult<Self>> + Send {
+        async move { borrow_bytes(reader, count) }
+    }
+}
+
+/// Takes the next `len` bytes of `reader` without copying them and moves
+/// past them.
+pub fn borrow_bytes<'de>(reader: &mut Cursor<&'de [u8]>, len: usize) -> BinResult<&'de [u8]> {
+    let rest = remaining(reader);
+    let Some(bytes) = rest.get(..len) else {
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "{len} bytes requested at 0x{:x} but only {} remain",
+                reader.position(),
+                rest.len()
+            ),
+        )));
+    };
+    reader.set_position(reader.position() + len as u64);
+    Ok(bytes)
+}
+
+/// The bytes of `reader` after its position.
+pub(crate) fn remaining<'de>(reader: &Cursor<&'de [u8]>) -> &'de [u8] {
+    let buf: &'de [u8] = reader.get_ref();
+    let pos = usize::try_from(reader.position()).unwrap_or(usize::MAX);
+    buf.get(pos..).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NullStr;
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_borrowed_fields() -> Result<()> {
+        let data = [0, 3, b'a', b'b', b'c', b'n', b'a', b'm', b'e', 0, 7];
+        let mut reader = Cursor::new(&data[..]);
+        let len = u16::read_borrowed(&mut reader, Endian::Big).await?;
+        let bytes = <&[u8]>::read_borrowed_options(&mut reader, Endian::Big, len.into()).await?;
+        let name = NullStr::read_borrowed(&mut reader, Endian::Big).await?;
+        let tail = u8::read_borrowed(&mut reader, Endian::Big).await?;
+
+        assert_eq!(bytes, b"abc");
+        assert!(core::ptr::eq(bytes.as_ptr(), &data[2]));
+        assert_eq!(name.to_str()?, "name");
+        assert!(core::ptr::eq(name.0.as_ptr(), &data[5]));
+        assert_eq!(tail, 7);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_borrowed_eof() {
+        let data = [1, 2, 3];
+        let mut reader = Cursor::new(&data[..]);
+        reader.set_position(1);
+        let err = <&[u8]>::read_borrowed_options(&mut reader, Endian::Little, 3)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(ref e) if e.kind() == ErrorKind::UnexpectedEof));
+        assert_eq!(reader.position(), 1);
+        assert!(
+            NullStr::read_borrowed(&mut reader, Endian::Little)
+                .await
+                .is_err()
+        );
+        assert_eq!(reader.position(), 1);
+    }
+}
@@ -61,6 +61,10 @@ read_tuple_impl!(
     b1, b2, b3, b4, b5, b6, b7, b8, b9, b10, b11, b12, b13, b14, b15, b16, b17, b18, b19, b20, b21,
     b22, b23, b24, b25, b26, b27, b28, b29, b30, b31, b32
 );
+/// The most memory a `Vec` read reserves before the data to fill it has been
+/// read.
+const MAX_PREALLOC_BYTES: usize = 64 * 1024;
+
 impl<B> BinRead for Vec<B>
 where
     B: BinRead + Send + 'static,
@@ -75,15 +79,38 @@ where
     ) -> impl Future<Output = BinResult<Self>> + Send {
         async move {
             let count = args;
-            if core::any::TypeId::of::<B>() == core::any::TypeId::of::<u8>() {
-                let mut list = vec![0u8; count];
-                reader
-                    .read_exact(list.as_mut_slice())
-                    .await
-                    .map_err(|e| crate::error::Error::Io(e))?;
-                return Ok(unsafe { core::mem::transmute(list) });
+            let is_bytes = core::any::TypeId::of::<B>() == core::any::TypeId::of::<u8>();
+            if let Some(policy) = reader.allocation_policy() {
+                // Every element of a sized type takes at least one byte.
+                let check_length = policy.check_length && core::mem::size_of::<B>() > 0;
+                let pos = reader.stream_position().await?;
+                if check_length {
+                    let remaining = reader.length().await?.saturating_sub(pos);
+                    let limit = usize::try_from(remaining).unwrap_or(usize::MAX);
+                    if count > limit {
+                        return Err(crate::Error::TooLarge { pos, count, limit });
+                    }
+                }
+                if let Some(policy) = reader.allocation_policy() {
+                    policy.reserve(pos, count, core::mem::size_of::<B>())?;
+                }
             }
-            let mut list = Vec::with_capacity(count);
+            // Grow the list as data actually arrives, so a corrupt count fails
+            // at the end of the stream instead of allocating all of it first.
+            if is_bytes {
+                let mut list = Vec::new();
+                while list.len() < count {
+                    let start = list.len();
+                    list.resize(start + (count - start).min(MAX_PREALLOC_BYTES), 0u8);
+                    reader
+                        .read_exact(&mut list[start..])
+                        .await
+                        .map_err(|e| crate::error::Error::Io(e))?;
+                }
+                return Ok(unsafe { core::mem::transmute::<Vec<u8>, Vec<B>>(list) });
+            }
+            let prealloc = MAX_PREALLOC_BYTES / core::mem::size_of::<B>().max(1);
+            let mut list = Vec::with_capacity(count.min(prealloc));
             let b_args = B::Args::default();
             for _ in 0..count {
                 list.push(B::read_options(reader, endian, b_args.clone()).await?);
@@ -179,7 +206,7 @@ impl BinRead for bool {
 #[cfg(test)]
 mod tests {
     use crate::io::{Read, Seek, Write};
-    use crate::{BinRead, BinReaderExt, BinResult, Endian};
+    use crate::{BinRead, BinReaderExt, BinResult, Endian, NamedArgs};
     use anyhow::Result;
     use std::io::{Cursor, SeekFrom};
     use std::marker::PhantomData;
@@ -282,12 +309,18 @@ mod tests {
 
     pub type ReadBytesFun<'a> =
         dyn FnMut(u64) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + 'a;
+    #[derive(NamedArgs)]
+    struct DirArgs<'a, C> {
+        index: u16,
+        config: &'a C,
+        on_progress: &'a mut ReadBytesFun<'a>,
+    }
     impl<T> BinRead for Dir<T>
     where
         T: Read + Write + Seek + Send + StreamDefault,
         T::Config: Config + 'static,
     {
-        type Args<'a> = (u16, &'a T::Config, &'a mut ReadBytesFun<'a>);
+        type Args<'a> = DirArgs<'a, T::Config>;
 
         fn read_options<R: Read + Seek + Send>(
             reader: &mut R,
@@ -298,8 +331,12 @@ mod tests {
             Self: Send,
         {
             async move {
-                let (index, config, callback) = args;
-                callback(1).await;
+                let DirArgs {
+                    index,
+                    config,
+                    on_progress,
+                } = args;
+                on_progress(1).await;
                 // let mut config = config.clone();
                 let (mut data, _) = T::from_ref_config(0, 0, config).await?;
                 let mut data = T::from_config(config).await?;
@@ -428,7 +465,13 @@ mod tests {
             let mut adapter = create_adapter(total_size, &mut bytes_count, callback);
 
             let dir: Dir<MyData> = cursor
-                .read_le_args((1, &config, &mut adapter as &mut ReadBytesFun))
+                .read_le_args(
+                    Dir::<MyData>::args()
+                        .index(1)
+                        .config(&config)
+                        .on_progress(&mut adapter)
+                        .finalize(),
+                )
                 .await?;
         }
         Ok(())
@@ -1,6 +1,9 @@
+pub mod borrowed;
 pub mod impls;
 
-use crate::Required;
+pub use borrowed::{BinReadBorrowed, borrow_bytes};
+
+use crate::{NamedArgs, Required};
 use crate::io::read::Read;
 use crate::io::seek::Seek;
 use crate::{BinResult, Endian};
@@ -50,6 +53,15 @@ pub trait BinRead: Sized {
         async move { Self::read_ne_args(reader, Self::Args::args()).await }
     }
 
+    /// Starts building this type's arguments by name.
+    #[inline]
+    fn args<'a>() -> <Self::Args<'a> as NamedArgs>::Builder
+    where
+        Self::Args<'a>: NamedArgs,
+    {
+        <Self::Args<'a> as NamedArgs>::builder()
+    }
+
     #[inline]
     fn read_args<R: Read + Seek + Send>(
         reader: &mut R,
@@ -178,3 +190,307 @@ pub trait BinReaderExt: Read + Seek + Sized + Send {
 }
 
 impl<R: Read + Seek + Sized + Send> BinReaderExt for R {}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{Read, Seek};
+    use crate::{BinRead, BinReaderExt, BinResult, Endian, Error};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(big, magic = 0x4d5au16)]
+    struct Header {
+        len: u8,
+        #[br(args_raw = len as usize)]
+        data: Vec<u8>,
+        #[br(little)]
+        flags: u16,
+        #[br(ignore)]
+        cached: Option<u32>,
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(import(count: usize))]
+    struct Block(#[br(args_raw = count)] Vec<u8>, u16);
+
+    #[tokio::test]
+    async fn test_derive_struct() -> Result<()> {
+        let mut data = Cursor::new(vec![0x4d, 0x5a, 2, 0xaa, 0xbb, 0x01, 0x00]);
+        let header: Header = data.read_le().await?;
+        assert_eq!(
+            header,
+            Header {
+                len: 2,
+                data: vec![0xaa, 0xbb],
+                flags: 1,
+                cached: None,
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_tuple_struct_imports() -> Result<()> {
+        let mut data = Cursor::new(vec![3, 0x01, 0x02]);
+        let block = Block::read_be_args(&mut data, (1,)).await?;
+        assert_eq!(block, Block(vec![3], 0x0102));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_bad_magic_rewinds() -> Result<()> {
+        let mut data = Cursor::new(vec![0x4d, 0x5b, 0, 0, 0]);
+        let err = Header::read_le(&mut data).await.unwrap_err();
+        assert!(matches!(err, Error::BadMagic { pos: 0, .. }));
+        assert_eq!(data.position(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_field_context() -> Result<()> {
+        let mut data = Cursor::new(vec![0x4d, 0x5a, 4, 0xaa]);
+        let err = Header::read_le(&mut data).await.unwrap_err();
+        let Error::Backtrace(backtrace) = err else {
+            panic!("expected a backtrace, got {err:?}");
+        };
+        assert!(matches!(*backtrace.error, Error::Io(_)));
+        assert!(err_message(&backtrace.frames[0]).contains("'data' in Header"));
+        assert_eq!(data.position(), 0);
+        Ok(())
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(little)]
+    enum Packet {
+        #[br(magic = 1u8)]
+        Wide(u32),
+        #[br(magic = 1u8)]
+        Narrow { value: u8 },
+        #[br(magic = 2u8)]
+        Empty,
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(return_unexpected_error)]
+    enum Strict {
+        #[br(magic = 1u8)]
+        Wide(u32),
+        #[br(magic = 1u8)]
+        Narrow(u8),
+    }
+
+    #[tokio::test]
+    async fn test_derive_enum_rewinds_between_variants() -> Result<()> {
+        let mut data = Cursor::new(vec![1, 5, 2]);
+        assert_eq!(Packet::read(&mut data).await?, Packet::Narrow { value: 5 });
+        assert_eq!(Packet::read(&mut data).await?, Packet::Empty);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_enum_errors() -> Result<()> {
+        let mut data = Cursor::new(vec![3, 0]);
+        let err = Packet::read(&mut data).await.unwrap_err();
+        let Error::EnumErrors {
+            pos,
+            variant_errors,
+        } = err
+        else {
+            panic!("expected EnumErrors, got {err:?}");
+        };
+        assert_eq!(pos, 0);
+        let names = variant_errors.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["Wide", "Narrow", "Empty"]);
+        assert!(
+            variant_errors
+                .iter()
+                .all(|(_, err)| matches!(err, Error::BadMagic { pos: 0, .. }))
+        );
+        assert_eq!(data.position(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_enum_unexpected_error() -> Result<()> {
+        let mut data = Cursor::new(vec![1, 5]);
+        let err = Strict::read(&mut data).await.unwrap_err();
+        assert!(matches!(err.root_cause(), Error::Io(_)));
+        assert_eq!(data.position(), 0);
+
+        let mut data = Cursor::new(vec![3, 5]);
+        let err = Strict::read(&mut data).await.unwrap_err();
+        assert!(matches!(err, Error::NoVariantMatch { pos: 0 }));
+        Ok(())
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(repr = u8)]
+    enum Opcode {
+        Nop,
+        Push = 0x10,
+        Pop,
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[repr(u16)]
+    enum Tag {
+        Start = 1,
+        End = 2,
+        #[br(fallback)]
+        Other(u16),
+    }
+
+    #[tokio::test]
+    async fn test_derive_unit_enum() -> Result<()> {
+        let mut data = Cursor::new(vec![0x00, 0x10, 0x11, 0x12]);
+        assert_eq!(Opcode::read(&mut data).await?, Opcode::Nop);
+        assert_eq!(Opcode::read(&mut data).await?, Opcode::Push);
+        assert_eq!(Opcode::read(&mut data).await?, Opcode::Pop);
+        let err = Opcode::read(&mut data).await.unwrap_err();
+        assert!(matches!(err, Error::NoVariantMatch { pos: 3 }));
+        assert_eq!(data.position(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_unit_enum_fallback() -> Result<()> {
+        let mut data = Cursor::new(vec![0, 2, 0, 9]);
+        assert_eq!(Tag::read_be(&mut data).await?, Tag::End);
+        assert_eq!(Tag::read_be(&mut data).await?, Tag::Other(9));
+        Ok(())
+    }
+
+    #[derive(crate::NamedArgs)]
+    struct ChunkArgs {
+        count: usize,
+        #[named_args(default = 1)]
+        step: u8,
+    }
+
+    #[test]
+    fn test_named_args_default() {
+        let args = ChunkArgs::builder().count(3).finalize();
+        assert_eq!((args.count, args.step), (3, 1));
+        let args = ChunkArgs::builder().step(2).count(4).finalize();
+        assert_eq!((args.count, args.step), (4, 2));
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(import { count: usize, scale: u8 = 1 })]
+    struct Samples {
+        #[br(args_raw = count * usize::from(scale))]
+        values: Vec<u8>,
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    struct Frame {
+        len: u8,
+        #[br(args { count: len as usize })]
+        plain: Samples,
+        #[br(args { count: 1, scale: 3 })]
+        scaled: Samples,
+    }
+
+    #[tokio::test]
+    async fn test_derive_named_imports() -> Result<()> {
+        let mut data = Cursor::new(vec![2, 1, 2, 3, 4, 5]);
+        let frame = Frame::read(&mut data).await?;
+        assert_eq!(frame.plain.values, [1, 2]);
+        assert_eq!(frame.scaled.values, [3, 4, 5]);
+
+        let mut data = Cursor::new(vec![4, 5, 6, 7]);
+        let samples = Samples::read_args(&mut data, Samples::args().count(2).scale(2).finalize())
+            .await?;
+        assert_eq!(samples.values, [4, 5, 6, 7]);
+        Ok(())
+    }
+
+    async fn parse_u24<R: Read + Seek + Send>(
+        reader: &mut R,
+        endian: Endian,
+        (): (),
+    ) -> BinResult<u32> {
+        let bytes = <[u8; 3]>::read_options(reader, endian, ()).await?;
+        Ok(match endian {
+            Endian::Big => u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]),
+            Endian::Little => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]),
+        })
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    struct Offsets {
+        #[br(parse_with = parse_u24)]
+        start: u32,
+        #[br(parse_with = parse_u24, big)]
+        end: u32,
+    }
+
+    #[tokio::test]
+    async fn test_derive_parse_with() -> Result<()> {
+        let mut data = Cursor::new(vec![1, 0, 0, 0, 0, 2]);
+        let offsets: Offsets = data.read_le().await?;
+        assert_eq!(offsets, Offsets { start: 1, end: 2 });
+        Ok(())
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct BadVersion(u8);
+
+    impl core::fmt::Display for BadVersion {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "unsupported version {}", self.0)
+        }
+    }
+
+    #[derive(BinRead, Debug, PartialEq)]
+    #[br(assert(lo <= hi, "empty range {}..{}", lo, hi))]
+    struct Range {
+        #[br(assert(version == 1, BadVersion(version)))]
+        version: u8,
+        lo: u8,
+        #[br(assert(hi < 100))]
+        hi: u8,
+    }
+
+    #[tokio::test]
+    async fn test_derive_assert() -> Result<()> {
+        let mut data = Cursor::new(vec![1, 2, 3]);
+        assert_eq!(
+            Range::read(&mut data).await?,
+            Range {
+                version: 1,
+                lo: 2,
+                hi: 3
+            }
+        );
+
+        let mut data = Cursor::new(vec![0, 2, 1, 2]);
+        data.set_position(1);
+        let err = Range::read(&mut data).await.unwrap_err();
+        let Error::Custom { pos, err } = err.root_cause() else {
+            panic!("expected a custom error, got {err:?}");
+        };
+        assert_eq!(*pos, 1);
+        assert_eq!(err.downcast_ref::<BadVersion>(), Some(&BadVersion(2)));
+        assert_eq!(data.position(), 1);
+
+        let err = Range::read(&mut Cursor::new(vec![1, 2, 100])).await.unwrap_err();
+        assert!(matches!(
+            err.root_cause(),
+            Error::AssertFail { pos: 2, message } if message == "assertion failed: `hi < 100`"
+        ));
+
+        let err = Range::read(&mut Cursor::new(vec![1, 5, 3])).await.unwrap_err();
+        assert_eq!(err.to_string(), "empty range 5..3 at 0x0");
+        Ok(())
+    }
+
+    fn err_message(frame: &crate::BacktraceFrame) -> String {
+        match frame {
+            crate::BacktraceFrame::Full { message, .. }
+            | crate::BacktraceFrame::Message(message) => message.to_string(),
+            crate::BacktraceFrame::Custom(err) => err.to_string(),
+        }
+    }
+}
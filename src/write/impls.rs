@@ -35,21 +35,23 @@ where
 {
     type Args<'a> = T::Args<'a>;
 
-    async fn write_options<W: Write + Seek + Send>(
+    fn write_options<W: Write + Seek + Send>(
         &self,
         writer: &mut W,
         endian: Endian,
         args: Self::Args<'_>,
-    ) -> BinResult<()> {
-        if let Some(this) = <dyn Any>::downcast_ref::<[u8; N]>(self) {
-            writer.write_all(&this[..]).await?;
-        } else {
-            for item in self {
-                T::write_options(item, writer, endian, args.clone()).await?;
+    ) -> impl Future<Output = BinResult<()>> + Send {
+        async move {
+            if let Some(this) = <dyn Any>::downcast_ref::<[u8; N]>(self) {
+                writer.write_all(&this[..]).await?;
+            } else {
+                for item in self {
+                    T::write_options(item, writer, endian, args.clone()).await?;
+                }
             }
-        }
 
-        Ok(())
+            Ok(())
+        }
     }
 }
 
@@ -60,17 +62,19 @@ where
 {
     type Args<'a> = T::Args<'a>;
 
-    async fn write_options<W: Write + Seek + Send>(
+    fn write_options<W: Write + Seek + Send>(
         &self,
         writer: &mut W,
         endian: Endian,
         args: Self::Args<'_>,
-    ) -> BinResult<()> {
-        for item in self {
-            T::write_options(item, writer, endian, args.clone()).await?;
-        }
+    ) -> impl Future<Output = BinResult<()>> + Send {
+        async move {
+            for item in self {
+                T::write_options(item, writer, endian, args.clone()).await?;
+            }
 
-        Ok(())
+            Ok(())
+        }
     }
 }
 
@@ -81,23 +85,25 @@ where
 {
     type Args<'a> = T::Args<'a>;
 
-    async fn write_options<W: Write + Seek + Send>(
+    fn write_options<W: Write + Seek + Send>(
         &self,
         writer: &mut W,
         endian: Endian,
         args: Self::Args<'_>,
-    ) -> BinResult<()> {
-        if let Some(this) = <dyn Any>::downcast_ref::<Vec<u8>>(self) {
-            writer.write_all(this).await?;
-        } else if let Some(this) = <dyn Any>::downcast_ref::<Vec<i8>>(self) {
-            writer.write_all(bytemuck::cast_slice(this.as_slice())).await?;
-        } else {
-            for item in self {
-                T::write_options(item, writer, endian, args.clone()).await?;
+    ) -> impl Future<Output = BinResult<()>> + Send {
+        async move {
+            if let Some(this) = <dyn Any>::downcast_ref::<Vec<u8>>(self) {
+                writer.write_all(this).await?;
+            } else if let Some(this) = <dyn Any>::downcast_ref::<Vec<i8>>(self) {
+                writer.write_all(bytemuck::cast_slice(this.as_slice())).await?;
+            } else {
+                for item in self {
+                    T::write_options(item, writer, endian, args.clone()).await?;
+                }
             }
-        }
 
-        Ok(())
+            Ok(())
+        }
     }
 }
 
@@ -121,19 +127,21 @@ impl<T: BinWrite + Sync + ?Sized + 'static> BinWrite for Box<T>
 {
     type Args<'a> = T::Args<'a>;
 
-    async fn write_options<W: Write + Seek + Send>(
+    fn write_options<W: Write + Seek + Send>(
         &self,
         writer: &mut W,
         endian: Endian,
         args: Self::Args<'_>,
-    ) -> BinResult<()> {
-        if let Some(this) = <dyn Any>::downcast_ref::<Box<[u8]>>(self) {
-            writer.write_all(this).await?;
-        } else {
-            (**self).write_options(writer, endian, args).await?;
-        }
+    ) -> impl Future<Output = BinResult<()>> + Send {
+        async move {
+            if let Some(this) = <dyn Any>::downcast_ref::<Box<[u8]>>(self) {
+                writer.write_all(this).await?;
+            } else {
+                (**self).write_options(writer, endian, args).await?;
+            }
 
-        Ok(())
+            Ok(())
+        }
     }
 }
 
@@ -141,15 +149,17 @@ impl<T: BinWrite + Sync> BinWrite for Option<T>
 {
     type Args<'a> = T::Args<'a>;
 
-    async fn write_options<W: Write + Seek + Send>(
+    fn write_options<W: Write + Seek + Send>(
         &self,
         writer: &mut W,
         endian: Endian,
         args: Self::Args<'_>,
-    ) -> BinResult<()> {
-        match self {
-            Some(inner) => inner.write_options(writer, endian, args).await,
-            None => Ok(()),
+    ) -> impl Future<Output = BinResult<()>> + Send {
+        async move {
+            match self {
+                Some(inner) => inner.write_options(writer, endian, args).await,
+                None => Ok(()),
+            }
         }
     }
 }
@@ -205,3 +205,321 @@ pub trait BinWriterExt: Write + Seek + Sized + Send {
 }
 
 impl<W: Write + Seek + Sized + Send> BinWriterExt for W {}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{Seek, Write};
+    use crate::{BinRead, BinReaderExt, BinResult, BinWrite, BinWriterExt, Endian};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(big, magic = 0x4d5au16)]
+    struct Header {
+        #[brw(little)]
+        version: u16,
+        #[brw(pad_before = 2, pad_after = 1)]
+        flags: u8,
+        #[brw(ignore)]
+        cached: Option<u32>,
+    }
+
+    async fn write_u24<W: Write + Seek + Send>(
+        value: &u32,
+        writer: &mut W,
+        endian: Endian,
+        (): (),
+    ) -> BinResult<()> {
+        let bytes = match endian {
+            Endian::Big => value.to_be_bytes()[1..].to_vec(),
+            Endian::Little => value.to_le_bytes()[..3].to_vec(),
+        };
+        writer.write_all(&bytes).await?;
+        Ok(())
+    }
+
+    #[derive(BinWrite)]
+    struct Record(
+        #[bw(map = |x: &bool| u8::from(*x))] bool,
+        #[bw(write_with = write_u24)] u32,
+    );
+
+    #[derive(BinWrite)]
+    #[bw(little)]
+    enum Command {
+        #[bw(magic = 1u8)]
+        Move { x: i16, y: i16 },
+        #[bw(magic = 2u8, big)]
+        Say(u16),
+        #[bw(magic = 3u8)]
+        Quit,
+    }
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq, Clone, Copy)]
+    #[brw(big)]
+    #[repr(u16)]
+    enum Kind {
+        File = 0x100,
+        Dir,
+        #[brw(fallback)]
+        Unknown(u16),
+    }
+
+    #[tokio::test]
+    async fn test_derive_struct_round_trip() -> Result<()> {
+        let header = Header {
+            version: 2,
+            flags: 7,
+            cached: Some(1),
+        };
+        let mut data = Cursor::new(Vec::new());
+        data.write_le(&header).await?;
+        assert_eq!(data.get_ref(), &[0x4d, 0x5a, 2, 0, 0, 0, 7, 0]);
+
+        data.set_position(0);
+        let read: Header = data.read_le().await?;
+        assert_eq!(read, Header { cached: None, ..header });
+        assert_eq!(data.position(), 8);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_write_with() -> Result<()> {
+        let mut data = Cursor::new(Vec::new());
+        Record(true, 0x010203).write_be(&mut data).await?;
+        assert_eq!(data.into_inner(), vec![1, 1, 2, 3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_enum() -> Result<()> {
+        let mut data = Cursor::new(Vec::new());
+        for command in [Command::Move { x: 1, y: -1 }, Command::Say(5), Command::Quit] {
+            command.write(&mut data).await?;
+        }
+        assert_eq!(data.into_inner(), vec![1, 1, 0, 0xff, 0xff, 2, 0, 5, 3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_unit_enum_round_trip() -> Result<()> {
+        let kinds = [Kind::File, Kind::Dir, Kind::Unknown(7)];
+        let mut data = Cursor::new(Vec::new());
+        for kind in kinds {
+            data.write_le(&kind).await?;
+        }
+        assert_eq!(data.get_ref(), &[1, 0, 1, 1, 0, 7]);
+
+        data.set_position(0);
+        for kind in kinds {
+            assert_eq!(data.read_le::<Kind>().await?, kind);
+        }
+        Ok(())
+    }
+
+    #[derive(BinWrite)]
+    #[bw(import { scale: u8 = 1 })]
+    struct Gain {
+        #[bw(map = |value: &u8| value * scale)]
+        value: u8,
+    }
+
+    #[derive(BinWrite)]
+    struct Mix {
+        #[bw(args { scale: 3 })]
+        left: Gain,
+        right: Gain,
+    }
+
+    #[tokio::test]
+    async fn test_derive_named_imports() -> Result<()> {
+        let mut data = Cursor::new(Vec::new());
+        let mix = Mix {
+            left: Gain { value: 2 },
+            right: Gain { value: 2 },
+        };
+        mix.write(&mut data).await?;
+        let args = GainBinWriteArgs::builder().scale(5).finalize();
+        Gain { value: 2 }.write_args(&mut data, args).await?;
+        assert_eq!(data.into_inner(), vec![6, 2, 10]);
+        Ok(())
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Level {
+        Low,
+        High,
+    }
+
+    #[derive(Debug)]
+    struct BadLevel(u8);
+
+    impl core::fmt::Display for BadLevel {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "unknown level {}", self.0)
+        }
+    }
+
+    impl TryFrom<u8> for Level {
+        type Error = BadLevel;
+
+        fn try_from(value: u8) -> Result<Self, BadLevel> {
+            match value {
+                0 => Ok(Self::Low),
+                1 => Ok(Self::High),
+                _ => Err(BadLevel(value)),
+            }
+        }
+    }
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(little)]
+    struct Chunk {
+        #[bw(calc = items.len() as u8)]
+        len: u8,
+        flags: u8,
+        #[br(count = len)]
+        items: Vec<u16>,
+        #[brw(if(flags & 1 != 0))]
+        extra: Option<u32>,
+        #[brw(if(flags & 2 != 0, 0xff))]
+        tag: u8,
+        #[br(try_map = |x: u8| Level::try_from(x))]
+        #[bw(map = |level: &Level| matches!(level, Level::High) as u8)]
+        level: Level,
+        #[br(map = |x: u8| u16::from(x))]
+        #[bw(try_map = |value: &u16| u8::try_from(*value))]
+        small: u16,
+        #[br(calc = u32::from(len) * 2)]
+        #[bw(ignore)]
+        size: u32,
+    }
+
+    #[tokio::test]
+    async fn test_derive_field_directives() -> Result<()> {
+        let chunk = Chunk {
+            len: 0,
+            flags: 1,
+            items: vec![0x0102, 0x0304],
+            extra: Some(5),
+            tag: 0xff,
+            level: Level::High,
+            small: 9,
+            size: 4,
+        };
+        let mut data = Cursor::new(Vec::new());
+        data.write_le(&chunk).await?;
+        assert_eq!(data.get_ref(), &[2, 1, 2, 1, 4, 3, 5, 0, 0, 0, 1, 9]);
+
+        data.set_position(0);
+        assert_eq!(data.read_le::<Chunk>().await?, Chunk { len: 2, ..chunk });
+
+        let mut data = Cursor::new(vec![0, 2, 7, 3, 0]);
+        let err = data.read_le::<Chunk>().await.unwrap_err();
+        let crate::Error::Custom { pos, err } = err.root_cause() else {
+            panic!("expected a custom error, got {err:?}");
+        };
+        assert_eq!(*pos, 3);
+        assert_eq!(err.to_string(), "unknown level 3");
+
+        let chunk = Chunk {
+            small: 300,
+            ..Chunk::read_le(&mut Cursor::new(vec![0, 2, 7, 1, 0])).await?
+        };
+        assert_eq!(chunk.tag, 7);
+        let err = Cursor::new(Vec::new()).write_le(&chunk).await.unwrap_err();
+        assert!(matches!(err.root_cause(), crate::Error::Custom { pos: 4, .. }));
+        Ok(())
+    }
+
+    #[crate::binrw]
+    #[brw(big)]
+    #[derive(Debug, PartialEq)]
+    struct Table {
+        #[br(temp)]
+        #[bw(calc = entries.len() as u16)]
+        count: u16,
+        #[br(temp)]
+        #[bw(calc = name.len() as u8)]
+        name_len: u8,
+        #[br(count = name_len)]
+        name: Vec<u8>,
+        #[br(count = count)]
+        entries: Vec<u32>,
+        #[br(temp)]
+        #[bw(calc = u8::from(extra.is_some()))]
+        flags: u8,
+        #[brw(if(flags & 1 != 0))]
+        extra: Option<u16>,
+    }
+
+    #[tokio::test]
+    async fn test_temp_fields_round_trip() -> Result<()> {
+        let bytes = [0, 2, 3, b'a', b'b', b'c', 0, 0, 0, 1, 0, 0, 0, 2, 1, 0, 9];
+        let table = Cursor::new(bytes).read_be::<Table>().await?;
+        assert_eq!(
+            table,
+            Table {
+                name: b"abc".to_vec(),
+                entries: vec![1, 2],
+                extra: Some(9),
+            }
+        );
+
+        let mut data = Cursor::new(Vec::new());
+        data.write_be(&table).await?;
+        assert_eq!(data.get_ref(), &bytes);
+
+        let table = Table {
+            extra: None,
+            ..table
+        };
+        let mut data = Cursor::new(Vec::new());
+        data.write_be(&table).await?;
+        assert_eq!(data.get_ref(), &[&bytes[..14], &[0]].concat());
+        Ok(())
+    }
+
+    #[crate::binrw]
+    #[brw(little, assert(lo <= hi, "empty range {}..{}", lo, hi))]
+    #[derive(Debug, PartialEq)]
+    struct Span {
+        #[br(temp)]
+        #[bw(calc = 1)]
+        version: u8,
+        #[brw(assert(lo < 10))]
+        lo: u8,
+        hi: u8,
+    }
+
+    #[derive(BinRead, BinWrite, Debug, PartialEq)]
+    #[brw(return_unexpected_error)]
+    enum Tagged {
+        #[brw(magic = 1u8)]
+        One(u8),
+        #[brw(magic = 2u8)]
+        Two(u16),
+    }
+
+    #[tokio::test]
+    async fn test_read_only_directives_on_brw() -> Result<()> {
+        let span = Span { lo: 2, hi: 3 };
+        let mut data = Cursor::new(Vec::new());
+        data.write_le(&span).await?;
+        assert_eq!(data.get_ref(), &[1, 2, 3]);
+        data.set_position(0);
+        assert_eq!(data.read_le::<Span>().await?, span);
+
+        let err = Cursor::new([1, 12, 13]).read_le::<Span>().await.unwrap_err();
+        assert!(matches!(err.root_cause(), crate::Error::AssertFail { pos: 1, .. }));
+        let err = Cursor::new([1, 5, 3]).read_le::<Span>().await.unwrap_err();
+        assert_eq!(err.to_string(), "empty range 5..3 at 0x0");
+
+        let mut data = Cursor::new(Vec::new());
+        data.write_le(&Tagged::Two(7)).await?;
+        assert_eq!(data.get_ref(), &[2, 7, 0]);
+        data.set_position(0);
+        assert_eq!(data.read_le::<Tagged>().await?, Tagged::Two(7));
+        Ok(())
+    }
+}